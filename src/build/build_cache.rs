@@ -0,0 +1,452 @@
+//! What the last build wrote and what from, so the next one only re-renders what changed
+//! each output file gets a fingerprint of everything it's made from: the page's source, the context it's rendered with
+//! (its metadata, the section's pages for index pages, larvae.yaml...) and its template along with every template
+//! it extends or includes
+//! it's also the manifest of what megalopa wrote to the output dir, so what a build doesn't write again is removed
+//! without touching files put there by hand
+//! fingerprints are kept between builds (and megalopa versions), so they're FNV hashes rather than DefaultHasher's
+//! which can change with the rust version
+
+use fnv::FnvHasher;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use super::build_error::BuildError;
+use super::default_templates::DEFAULT_TEMPLATES;
+use super::page_renderer::PageContext;
+use super::path_utils::url_path;
+use crate::proj_config_utils::TemplateEngineKind;
+
+/// Where the cache is kept, relative to the project dir
+pub const CACHE_PATH: &str = ".megalopa/cache";
+
+/// The cache as it's saved between builds
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct CacheFile {
+    /// Fingerprints from another version of megalopa might not mean the same thing
+    version: String,
+    /// The outputs are only this build's if it writes to the same dir
+    #[serde(default)]
+    output_dir: PathBuf,
+    /// Output file (relative to the output dir) -> fingerprint of what it was built from
+    outputs: BTreeMap<PathBuf, u64>,
+}
+
+#[derive(Debug, Default)]
+pub struct BuildCache {
+    output_dir: PathBuf,
+    /// Template name (without the extension) -> hash of it and the templates it extends or includes
+    template_hashes: BTreeMap<String, u64>,
+    previous: CacheFile,
+    /// Whether previous's fingerprints can be trusted, it's outputs are still what the last build wrote either way
+    reuse: bool,
+    current: Mutex<CacheFile>,
+    /// How many outputs were fresh, the rest of current's were built
+    unchanged: AtomicUsize,
+}
+
+impl BuildCache {
+    /// Reads the last build's cache, every page's built unless it's fresh in it
+    /// (it isn't with force, or when it's missing, from another version or for another output dir)
+    pub fn load(
+        proj_dir: &Path,
+        output_dir: &Path,
+        template_hashes: BTreeMap<String, u64>,
+        force: bool,
+    ) -> BuildCache {
+        let version = env!("CARGO_PKG_VERSION").to_string();
+        let previous = fs::read_to_string(proj_dir.join(CACHE_PATH))
+            .ok()
+            .and_then(|cache_str| serde_yaml::from_str::<CacheFile>(&cache_str).ok())
+            .filter(|cache_file| cache_file.output_dir == output_dir)
+            .unwrap_or_default();
+        let reuse = !force && previous.version == version;
+        BuildCache {
+            output_dir: output_dir.to_path_buf(),
+            template_hashes,
+            previous,
+            reuse,
+            current: Mutex::new(CacheFile {
+                version,
+                output_dir: output_dir.to_path_buf(),
+                outputs: BTreeMap::new(),
+            }),
+            unchanged: AtomicUsize::new(0),
+        }
+    }
+
+    /// Fingerprint of a page rendered with template_name and context (without the content) from sources
+    pub fn page_fingerprint(
+        &self,
+        template_name: &str,
+        context: &PageContext,
+        sources: &[&str],
+    ) -> io::Result<u64> {
+        let mut hasher = FnvHasher::default();
+        self.template_hashes.get(template_name).hash(&mut hasher);
+        serde_yaml::to_string(context)
+            .map_err(io::Error::other)?
+            .hash(&mut hasher);
+        sources.hash(&mut hasher);
+        Ok(hasher.finish())
+    }
+
+    /// Whether output_path was built from the same fingerprint last build (and is still there)
+    /// it's carried over to this build's cache if so
+    /// something else already writing to it this build (IE: the generated robots.txt) makes it stale too
+    pub fn is_fresh(&self, output_path: &Path, fingerprint: u64) -> bool {
+        let relative_path = self.relative_path(output_path);
+        let mut current = self.current.lock().unwrap();
+        let is_fresh = self.reuse
+            && !current.outputs.contains_key(&relative_path)
+            && self.previous.outputs.get(&relative_path) == Some(&fingerprint)
+            && output_path.is_file();
+        if is_fresh {
+            current.outputs.insert(relative_path, fingerprint);
+            self.unchanged.fetch_add(1, Ordering::Relaxed);
+        }
+        is_fresh
+    }
+
+    /// Records what output_path was just built from
+    pub fn record(&self, output_path: &Path, fingerprint: u64) {
+        let relative_path = self.relative_path(output_path);
+        let mut current = self.current.lock().unwrap();
+        current.outputs.insert(relative_path, fingerprint);
+    }
+
+    /// Writes a file that's made again every build (IE: a feed), recording it like a rendered page
+    pub fn write_output(&self, output_path: &Path, contents: &str) -> Result<(), BuildError> {
+        fs::write(output_path, contents).map_err(|e| BuildError::io(output_path, e))?;
+        let mut hasher = FnvHasher::default();
+        hasher.write(contents.as_bytes());
+        self.record(output_path, hasher.finish());
+        Ok(())
+    }
+
+    /// Removes everything the last build wrote (and nothing else), so every file's built again
+    pub fn clean(&mut self) -> Vec<BuildError> {
+        let outputs = std::mem::take(&mut self.previous.outputs);
+        outputs
+            .keys()
+            .filter_map(|relative_path| self.remove_output(relative_path).err())
+            .collect()
+    }
+
+    /// Removes what the last build wrote that this one didn't (IE: a deleted post's page)
+    pub fn remove_stale(&self) -> Vec<BuildError> {
+        let current = self.current.lock().unwrap();
+        self.previous
+            .outputs
+            .keys()
+            .filter(|relative_path| !current.outputs.contains_key(*relative_path))
+            .filter_map(|relative_path| self.remove_output(relative_path).err())
+            .collect()
+    }
+
+    /// Keeps what the last build wrote that this one didn't in the cache instead,
+    /// a build that failed might not have written a page because it's the one that failed
+    pub fn keep_stale(&self) {
+        let mut current = self.current.lock().unwrap();
+        for (relative_path, fingerprint) in &self.previous.outputs {
+            current
+                .outputs
+                .entry(relative_path.clone())
+                .or_insert(*fingerprint);
+        }
+    }
+
+    /// How many outputs were built and how many were left as they were, so far
+    pub fn counts(&self) -> (usize, usize) {
+        let unchanged = self.unchanged.load(Ordering::Relaxed);
+        let recorded = self.current.lock().unwrap().outputs.len();
+        (recorded - unchanged, unchanged)
+    }
+
+    /// Writes this build's cache for the next one
+    pub fn save(&self, proj_dir: &Path) -> io::Result<()> {
+        let cache_path = proj_dir.join(CACHE_PATH);
+        fs::create_dir_all(cache_path.parent().unwrap())?;
+        let current = self.current.lock().unwrap();
+        let cache_str = serde_yaml::to_string(&*current).map_err(io::Error::other)?;
+        fs::write(cache_path, cache_str)
+    }
+
+    /// Removes an output and the dirs it leaves empty (dirs with anything else in them stay)
+    fn remove_output(&self, relative_path: &Path) -> Result<(), BuildError> {
+        let output_path = self.output_dir.join(relative_path);
+        match fs::remove_file(&output_path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(BuildError::io(&output_path, e)),
+        }
+        let mut dir = output_path.parent();
+        while let Some(dir_path) = dir {
+            if dir_path == self.output_dir || fs::remove_dir(dir_path).is_err() {
+                break;
+            }
+            dir = dir_path.parent();
+        }
+        Ok(())
+    }
+
+    fn relative_path(&self, output_path: &Path) -> PathBuf {
+        output_path
+            .strip_prefix(&self.output_dir)
+            .unwrap_or(output_path)
+            .to_path_buf()
+    }
+}
+
+/// Fingerprint of a file's contents (IE: a static file)
+pub fn file_fingerprint(f_path: &Path) -> io::Result<u64> {
+    let mut hasher = FnvHasher::default();
+    hasher.write(&fs::read(f_path)?);
+    Ok(hasher.finish())
+}
+
+/// Hashes every template the engine would load (the defaults, overridden by the project's templates dir)
+/// along with the templates each one extends or includes
+/// and the site's fingerprint for the ones that look up sections or pages (any of them could change)
+pub fn template_hashes(
+    templates_dir: &Path,
+    template_engine: &TemplateEngineKind,
+    site_fingerprint: u64,
+) -> io::Result<BTreeMap<String, u64>> {
+    let extension = match template_engine {
+        TemplateEngineKind::Tera => "html",
+        TemplateEngineKind::Mustache => "mustache",
+    };
+    let mut sources: BTreeMap<String, String> = BTreeMap::new();
+    for (file_name, source) in DEFAULT_TEMPLATES {
+        if let Some(name) = file_name.strip_suffix(&format!(".{extension}")) {
+            sources.insert(name.to_string(), source.to_string());
+        }
+    }
+    if templates_dir.is_dir() {
+        // tera loads the dir's sub dirs too, mustache only the dir itself
+        let recursive = *template_engine == TemplateEngineKind::Tera;
+        read_template_sources(
+            templates_dir,
+            templates_dir,
+            extension,
+            recursive,
+            &mut sources,
+        )?;
+    }
+    let dependencies: BTreeMap<&str, Option<Vec<String>>> = sources
+        .iter()
+        .map(|(name, source)| {
+            let dependencies = template_dependencies(source, template_engine);
+            (name.as_str(), dependencies)
+        })
+        .collect();
+
+    let mut template_hashes = BTreeMap::new();
+    for name in sources.keys() {
+        let mut hasher = FnvHasher::default();
+        let mut visited = BTreeSet::new();
+        hash_template(name, &sources, &dependencies, &mut visited, &mut hasher);
+        let uses_site = visited.iter().any(|name| {
+            let source = &sources[*name];
+            source.contains("get_section") || source.contains("get_page")
+        });
+        if uses_site {
+            site_fingerprint.hash(&mut hasher);
+        }
+        template_hashes.insert(name.clone(), hasher.finish());
+    }
+    Ok(template_hashes)
+}
+
+fn read_template_sources(
+    dir: &Path,
+    templates_dir: &Path,
+    extension: &str,
+    recursive: bool,
+    sources: &mut BTreeMap<String, String>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() && recursive {
+            read_template_sources(&path, templates_dir, extension, recursive, sources)?;
+        } else if path.extension().is_some_and(|ext| ext == extension) {
+            let mut name = path.strip_prefix(templates_dir).unwrap().to_path_buf();
+            name.set_extension("");
+            sources.insert(url_path(&name), fs::read_to_string(&path)?);
+        }
+    }
+    Ok(())
+}
+
+/// The names of the templates a template extends or includes
+/// None when one is only known while rendering (IE: a mustache {{> *name}}), then it could be any of them
+fn template_dependencies(
+    source: &str,
+    template_engine: &TemplateEngineKind,
+) -> Option<Vec<String>> {
+    let mut dependencies = vec![];
+    match template_engine {
+        TemplateEngineKind::Tera => {
+            let regex =
+                Regex::new(r#"\{%-?\s*(?:extends|include|import)\s+(?:"([^"]*)"|'([^']*)'|\S)"#)
+                    .unwrap();
+            for captures in regex.captures_iter(source) {
+                // anything but a quoted name (IE: a list of names to try)
+                let name = captures.get(1).or(captures.get(2))?.as_str();
+                dependencies.push(name.strip_suffix(".html").unwrap_or(name).to_string());
+            }
+        }
+        TemplateEngineKind::Mustache => {
+            let regex = Regex::new(r"\{\{\s*[<>]\s*(\*)?\s*([^\s}]+)").unwrap();
+            for captures in regex.captures_iter(source) {
+                if captures.get(1).is_some() {
+                    return None;
+                }
+                dependencies.push(captures[2].to_string());
+            }
+        }
+    }
+    Some(dependencies)
+}
+
+/// Hashes the template and (once each) the templates it depends on
+fn hash_template<'a>(
+    name: &str,
+    sources: &'a BTreeMap<String, String>,
+    dependencies: &BTreeMap<&'a str, Option<Vec<String>>>,
+    visited: &mut BTreeSet<&'a str>,
+    hasher: &mut FnvHasher,
+) {
+    let Some((name, source)) = sources.get_key_value(name) else {
+        // a missing template fails the render, hash that it's missing
+        name.hash(hasher);
+        return;
+    };
+    if !visited.insert(name.as_str()) {
+        return;
+    }
+    name.hash(hasher);
+    source.hash(hasher);
+    match dependencies.get(name.as_str()) {
+        Some(Some(names)) => {
+            for dependency in names {
+                hash_template(dependency, sources, dependencies, visited, hasher);
+            }
+        }
+        // could be any of them
+        _ => {
+            for other in sources.keys() {
+                hash_template(other, sources, dependencies, visited, hasher);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proj_config_utils::parse_config;
+
+    #[test]
+    pub fn fingerprints_dont_change_between_builds() {
+        let dir = tempfile::tempdir().unwrap();
+        let f_path = dir.path().join("hello.txt");
+        fs::write(&f_path, "hello").unwrap();
+        // FNV-1a of "hello", the same whichever rust version built megalopa
+        assert_eq!(file_fingerprint(&f_path).unwrap(), 0xa430d84680aabd0b);
+    }
+
+    #[test]
+    pub fn templates_hash_with_what_they_extend() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("post.html"),
+            "{% include \"footer.html\" %}",
+        )
+        .unwrap();
+        fs::write(dir.path().join("footer.html"), "footer").unwrap();
+        let before = template_hashes(dir.path(), &TemplateEngineKind::Tera, 0).unwrap();
+        fs::write(
+            dir.path().join("base.html"),
+            "<html>{% block content %}{% endblock content %}</html>",
+        )
+        .unwrap();
+        let after = template_hashes(dir.path(), &TemplateEngineKind::Tera, 0).unwrap();
+        // content extends base, post doesn't
+        assert_ne!(before["content"], after["content"]);
+        assert_eq!(before["post"], after["post"]);
+        fs::write(dir.path().join("footer.html"), "new footer").unwrap();
+        let after_footer = template_hashes(dir.path(), &TemplateEngineKind::Tera, 0).unwrap();
+        assert_ne!(after["post"], after_footer["post"]);
+
+        // a partial only known while rendering could be any of them
+        fs::write(dir.path().join("post.mustache"), "{{> *partial}}").unwrap();
+        fs::write(dir.path().join("footer.mustache"), "footer").unwrap();
+        let before = template_hashes(dir.path(), &TemplateEngineKind::Mustache, 0).unwrap();
+        fs::write(dir.path().join("footer.mustache"), "new footer").unwrap();
+        let after = template_hashes(dir.path(), &TemplateEngineKind::Mustache, 0).unwrap();
+        assert_ne!(before["post"], after["post"]);
+        assert_eq!(before["content"], after["content"]);
+    }
+
+    #[test]
+    pub fn unchanged_pages_are_fresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_dir = dir.path().join("public");
+        fs::create_dir(&output_dir).unwrap();
+        let page_path = output_dir.join("post.html");
+        let (site, _) = parse_config("title: Site").unwrap();
+        let context = PageContext {
+            content: "",
+            title: "Site",
+            site: &site,
+            content_title: Some("Post"),
+            page: None,
+            file_metadata_vec: None,
+            paginator: None,
+            dir_metadata_vec: None,
+            taxonomy: None,
+            term: None,
+        };
+        let template_hashes =
+            template_hashes(&dir.path().join("templates"), &TemplateEngineKind::Tera, 0).unwrap();
+
+        let cache = BuildCache::load(dir.path(), &output_dir, template_hashes.clone(), false);
+        let fingerprint = cache
+            .page_fingerprint("content", &context, &["# Post"])
+            .unwrap();
+        assert!(!cache.is_fresh(&page_path, fingerprint));
+        fs::write(&page_path, "rendered").unwrap();
+        cache.record(&page_path, fingerprint);
+        assert_eq!(cache.counts(), (1, 0));
+        cache.save(dir.path()).unwrap();
+
+        let cache = BuildCache::load(dir.path(), &output_dir, template_hashes.clone(), false);
+        assert!(cache.is_fresh(&page_path, fingerprint));
+        assert_eq!(cache.counts(), (0, 1));
+        let edited = cache
+            .page_fingerprint("content", &context, &["# Edited"])
+            .unwrap();
+        assert!(!cache.is_fresh(&page_path, edited));
+        let other_template = cache
+            .page_fingerprint("index", &context, &["# Post"])
+            .unwrap();
+        assert!(!cache.is_fresh(&page_path, other_template));
+
+        // something else wrote it earlier this build
+        let overwritten = BuildCache::load(dir.path(), &output_dir, template_hashes.clone(), false);
+        overwritten.record(&page_path, edited);
+        assert!(!overwritten.is_fresh(&page_path, fingerprint));
+
+        let forced = BuildCache::load(dir.path(), &output_dir, template_hashes, true);
+        assert!(!forced.is_fresh(&page_path, fingerprint));
+    }
+}
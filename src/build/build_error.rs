@@ -0,0 +1,170 @@
+//! What can go wrong building a site, each with the file it went wrong in
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::proj_config_utils::ConfigError;
+
+#[derive(Debug)]
+pub enum BuildError {
+    /// larvae.yaml couldn't be read or didn't pass validation
+    Config(ConfigError),
+    /// A file (or dir) couldn't be read or written
+    Io { path: PathBuf, error: io::Error },
+    /// A page's (or index.md's) frontmatter isn't yaml or has a known field that isn't valid
+    Frontmatter { path: PathBuf, message: String },
+    /// The templates dir has a template that doesn't parse
+    Templates { path: PathBuf, message: String },
+    /// A page couldn't be rendered (IE: it's layout doesn't exist or the template failed)
+    Render { path: PathBuf, message: String },
+    /// More than one page (or a page and a taxonomy) would be written to output_path, none of the pages are
+    Collision {
+        output_path: PathBuf,
+        sources: Vec<String>,
+    },
+}
+
+impl BuildError {
+    pub fn io(path: &Path, error: io::Error) -> BuildError {
+        BuildError::Io {
+            path: path.to_path_buf(),
+            error,
+        }
+    }
+
+    pub fn frontmatter(path: &Path, message: impl fmt::Display) -> BuildError {
+        BuildError::Frontmatter {
+            path: path.to_path_buf(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn render(path: &Path, message: impl fmt::Display) -> BuildError {
+        BuildError::Render {
+            path: path.to_path_buf(),
+            message: message.to_string(),
+        }
+    }
+
+    /// The file it went wrong in (larvae.yaml for the config, where it would be written for collisions)
+    pub fn path(&self) -> &Path {
+        match self {
+            BuildError::Config(_) => Path::new("larvae.yaml"),
+            BuildError::Io { path, .. }
+            | BuildError::Frontmatter { path, .. }
+            | BuildError::Templates { path, .. }
+            | BuildError::Render { path, .. } => path,
+            BuildError::Collision { output_path, .. } => output_path,
+        }
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::Config(e) => write!(f, "{e}"),
+            BuildError::Io { path, error } => write!(f, "{}: {error}", path.display()),
+            BuildError::Frontmatter { path, message } => {
+                write!(f, "Invalid frontmatter in {}\n{message}", path.display())
+            }
+            BuildError::Templates { path, message } => {
+                write!(f, "Invalid templates in {}\n{message}", path.display())
+            }
+            BuildError::Render { path, message } => {
+                write!(f, "Couldn't render {}\n{message}", path.display())
+            }
+            BuildError::Collision {
+                output_path,
+                sources,
+            } => write!(
+                f,
+                "More than one page would be written to {}\n{}",
+                output_path.display(),
+                sources.join(", ")
+            ),
+        }
+    }
+}
+
+impl Error for BuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BuildError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<ConfigError> for BuildError {
+    fn from(e: ConfigError) -> Self {
+        BuildError::Config(e)
+    }
+}
+
+/// What a build did, pages that fail don't stop the build (the rest are still built), they're listed here
+#[derive(Debug, Default)]
+pub struct BuildReport {
+    /// Files rendered or copied
+    pub built: usize,
+    /// Files left as they were, nothing they're made from changed
+    pub unchanged: usize,
+    /// In the order the site is built in
+    pub failures: Vec<BuildError>,
+}
+
+impl BuildReport {
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for BuildReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Built {} files ({} unchanged)",
+            self.built, self.unchanged
+        )?;
+        if !self.is_success() {
+            let count = self.failures.len();
+            let files = if count == 1 { "file" } else { "files" };
+            write!(f, ", {count} {files} failed:")?;
+            for failure in &self.failures {
+                write!(f, "\n\n{failure}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn report_lists_every_failure() {
+        let mut report = BuildReport {
+            built: 3,
+            unchanged: 1,
+            failures: vec![],
+        };
+        assert!(report.is_success());
+        assert_eq!(report.to_string(), "Built 3 files (1 unchanged)");
+        report.failures.push(BuildError::frontmatter(
+            Path::new("content/a.md"),
+            "date: \"soon\" isn't a date",
+        ));
+        report.failures.push(BuildError::render(
+            Path::new("content/b.md"),
+            "There's no \"post\" template",
+        ));
+        assert!(!report.is_success());
+        assert_eq!(report.failures[1].path(), Path::new("content/b.md"));
+        assert_eq!(
+            report.to_string(),
+            "Built 3 files (1 unchanged), 2 files failed:\n\nInvalid frontmatter in content/a.md\ndate: \"soon\" isn't a date\n\nCouldn't render content/b.md\nThere's no \"post\" template"
+        );
+    }
+}
@@ -1,59 +1,202 @@
-use std::{path, fs, io};
-use tera::Tera;
-use crate::{build::path_utils::{get_relative_file_path, get_relative_file_path_for_routing}, markdown_parsing::parse_markdown, proj_config_utils::{get_project_dir, read_config}};
-use super::parse_md::{ContentFileMetadata, IndexFileMetadata};
+use std::{path, fs};
+use chrono::NaiveDateTime;
+use crate::{build::path_utils::get_relative_file_path, markdown_parsing::parse_markdown};
+use super::build_error::BuildError;
+use super::page_renderer::{render_to_file, PageContext};
+use super::BuildContext;
+use super::parse_md::{ContentFileMetadata, IndexFileMetadata, MdIndexFileFrontmatter};
+use super::section_listing::{paginate, Paginator};
 
 /// md -> html content -> injected into template
 /// also writes to the same path in the /public dir
 pub fn build_md_file(
     file_path: path::PathBuf,
-    tera: &Tera,
-    content_f_metadata_vec: &Vec<ContentFileMetadata>,
-    index_f_metadata_vec: &Vec<IndexFileMetadata>,
-) -> io::Result<()> {
-    assert_eq!(file_path.extension().unwrap(), "md");
+    build_context: &BuildContext,
+    content_f_metadata_vec: &[ContentFileMetadata],
+    index_f_metadata_vec: &[IndexFileMetadata],
+    section_frontmatter: &MdIndexFileFrontmatter,
+) -> Result<(), BuildError> {
+    let to_build_error = |e| BuildError::io(&file_path, e);
+    let file_path_relative_to_content_dir =
+        get_relative_file_path(&file_path, &build_context.content_dir).map_err(to_build_error)?;
+    let mut build_file_path = build_context.output_dir.join(&file_path_relative_to_content_dir);
 
-    let proj_path = get_project_dir();
-    let proj_config = read_config(&proj_path);
+    let md_str = fs::read_to_string(&file_path).map_err(to_build_error)?;
 
-    let file_path_relative_to_content_dir = get_relative_file_path(&file_path, "content");
-    let mut build_file_path = proj_path.join("public").join(file_path_relative_to_content_dir);
+    let banner = content_f_metadata_vec
+        .iter()
+        .find(|f_metadata| f_metadata.source_path.eq(&file_path))
+        .and_then(|f_metadata| unpublished_banner(f_metadata, build_context.now))
+        .unwrap_or_default();
 
-    let md_str = fs::read_to_string(file_path.clone())?;
+    // index pages are split into a page per paginator
+    let mut paginators: Vec<Paginator> = vec![];
+    let mut context = PageContext {
+        content: "",
+        title: &build_context.config.title,
+        site: build_context.config,
+        content_title: None,
+        page: None,
+        file_metadata_vec: None,
+        paginator: None,
+        dir_metadata_vec: None,
+        taxonomy: None,
+        term: None,
+    };
 
-    let html_contents = parse_markdown(&md_str);
-
-    let mut context = tera::Context::new();
-    context.insert("content", &html_contents);
-    context.insert("title", &proj_config.title);
-
-    let file_contents: String;
+    let template_name: &str;
 
     // we need to decide what type of file this is... then use the corresponding template
     // easier to reason with the relative path
-    let relative_path = get_relative_file_path_for_routing(&build_file_path, "public");
+    let mut relative_path = file_path_relative_to_content_dir;
+    relative_path.set_extension("");
+    // the dir it's in, relative to the content dir ("" for the homepage's)
+    let section_path = relative_path
+        .parent()
+        .ok_or_else(|| BuildError::render(&file_path, "It isn't in the content dir"))?;
     if relative_path.ends_with("index") {
-        if let None = relative_path.parent().unwrap().parent() {
+        if section_path.parent().is_none() {
             // build homepage
-            context.insert("dir_metadata_vec", index_f_metadata_vec);
-            file_contents = tera.render("homepage.html", &context).unwrap();
+            context.dir_metadata_vec = Some(index_f_metadata_vec);
+            template_name = section_frontmatter.layout.as_deref().unwrap_or("homepage");
         } else {
             // build index page
-            context.insert("file_metadata_vec", content_f_metadata_vec);
-            file_contents = tera.render("index.html", &context).unwrap();
+            paginators = paginate(content_f_metadata_vec, section_frontmatter.paginate_by, section_path);
+            template_name = section_frontmatter.layout.as_deref().unwrap_or("index");
         }
     } else {
         // build content page
         let f_metadata = content_f_metadata_vec
-            .into_iter()
-            .find(|f_metadata| f_metadata.path.eq(&relative_path))
-            .unwrap();
-        context.insert("content_title", &f_metadata.title);
-        file_contents = tera.render("content.html", &context).unwrap();
+            .iter()
+            .find(|f_metadata| f_metadata.source_path.eq(&file_path))
+            .ok_or_else(|| {
+                BuildError::render(&file_path, "It isn't one of it's section's pages (IE: it was added after the site was read)")
+            })?;
+        context.content_title = Some(&f_metadata.title);
+        context.page = Some(f_metadata);
+        // the path has the frontmatter's slug
+        build_file_path = build_context.output_dir.join(&f_metadata.path);
+        // the page's layout, then the section's index.md, then larvae.yaml's section_templates
+        let section = section_path.to_string_lossy();
+        template_name = f_metadata
+            .frontmatter
+            .layout
+            .as_ref()
+            .or(section_frontmatter.page_layout.as_ref())
+            .or(build_context.config.section_templates.get(section.as_ref()))
+            .map_or("content", String::as_str);
+    }
+
+    if !build_context.page_renderer.has_template(template_name) {
+        return Err(BuildError::render(&file_path, format!(
+            "There's no \"{template_name}\" template (layouts are template names without the extension, IE: post for templates/post.html)"
+        )));
+    }
+
+    let build_file_paths: Vec<path::PathBuf> = if paginators.is_empty() {
+        build_file_path.set_extension("html");
+        vec![build_file_path]
+    } else {
+        paginators
+            .iter()
+            .map(|paginator| build_context.output_dir.join(&paginator.output_path))
+            .collect()
+    };
+    // the fingerprints leave out the content, it's made from md_str (and the banner)
+    let cache = &build_context.cache;
+    let mut stale = vec![];
+    for (i, build_file_path) in build_file_paths.iter().enumerate() {
+        if let Some(paginator) = paginators.get(i) {
+            context.file_metadata_vec = Some(paginator.pages);
+            context.paginator = Some(paginator);
+        }
+        let fingerprint = cache
+            .page_fingerprint(template_name, &context, &[&md_str, &banner])
+            .map_err(to_build_error)?;
+        let is_stale = match &build_context.page_stream {
+            // only the streamed page is rendered
+            Some(page_stream) => page_stream.build_file_path == *build_file_path,
+            None => !cache.is_fresh(build_file_path, fingerprint),
+        };
+        if is_stale {
+            stale.push((i, fingerprint));
+        }
+    }
+    if stale.is_empty() {
+        return Ok(());
     }
 
-    build_file_path.set_extension("html");
-    fs::create_dir_all(build_file_path.parent().unwrap())?;
-    fs::write(build_file_path, file_contents)?;
+    // only parsed when the page (or one of the index's pages) needs rendering
+    let html_contents = banner + &parse_markdown(&md_str);
+    context.content = &html_contents;
+    let page_renderer = build_context.page_renderer;
+    for (i, fingerprint) in stale {
+        if let Some(paginator) = paginators.get(i) {
+            context.file_metadata_vec = Some(paginator.pages);
+            context.paginator = Some(paginator);
+        }
+        let build_file_path = &build_file_paths[i];
+        if let Some(page_stream) = &build_context.page_stream {
+            page_stream.render(page_renderer, template_name, &context, &file_path)?;
+            continue;
+        }
+        render_to_file(page_renderer, template_name, &context, build_file_path, &file_path)?;
+        cache.record(build_file_path, fingerprint);
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Pages that only made it into the build through --drafts or --future say so at the top
+fn unpublished_banner(f_metadata: &ContentFileMetadata, now: NaiveDateTime) -> Option<String> {
+    let banner_text = match f_metadata.frontmatter.publish_date {
+        _ if f_metadata.frontmatter.draft => "Draft".to_string(),
+        Some(publish_date) if publish_date > now => {
+            format!("Scheduled for {}", publish_date.format("%Y-%m-%d %H:%M"))
+        }
+        _ => return None,
+    };
+    Some(format!(
+        "<div class=\"unpublished-banner\" style=\"padding: 0.5em; background: #ffe08a; color: #000; text-align: center;\">{banner_text}</div>"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proj_config_utils::parse_config;
+    use chrono::Local;
+    use tera::Tera;
+
+    #[test]
+    pub fn pages_missing_from_their_section_fail_with_their_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let content_dir = dir.path().join("content");
+        fs::create_dir_all(content_dir.join("posts")).unwrap();
+        let file_path = content_dir.join("posts/new.md");
+        fs::write(&file_path, "# New\n").unwrap();
+
+        let (config, _) = parse_config("title: Site").unwrap();
+        let build_context = BuildContext {
+            options: Default::default(),
+            now: Local::now().naive_local(),
+            content_dir,
+            output_dir: dir.path().join("public"),
+            static_dir: dir.path().join("static"),
+            config: &config,
+            page_renderer: &Tera::default(),
+            cache: Default::default(),
+            page_stream: None,
+        };
+        // the section was read before the page was added
+        let error = build_md_file(
+            file_path.clone(),
+            &build_context,
+            &[],
+            &[],
+            &MdIndexFileFrontmatter::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(error, BuildError::Render { .. }));
+        assert_eq!(error.path(), file_path);
+    }
+}
@@ -0,0 +1,45 @@
+//! The templates every project starts with, baked into the binary
+//! A project's own templates/ dir overrides them file by file
+
+/// (file name, contents) for both engines, tera's *.html and mustache's *.mustache
+pub const DEFAULT_TEMPLATES: [(&str, &str); 12] = [
+    ("base.html", include_str!("../../templates/base.html")),
+    ("content.html", include_str!("../../templates/content.html")),
+    (
+        "homepage.html",
+        include_str!("../../templates/homepage.html"),
+    ),
+    ("index.html", include_str!("../../templates/index.html")),
+    (
+        "taxonomy.html",
+        include_str!("../../templates/taxonomy.html"),
+    ),
+    (
+        "taxonomy_term.html",
+        include_str!("../../templates/taxonomy_term.html"),
+    ),
+    (
+        "base.mustache",
+        include_str!("../../templates/base.mustache"),
+    ),
+    (
+        "content.mustache",
+        include_str!("../../templates/content.mustache"),
+    ),
+    (
+        "homepage.mustache",
+        include_str!("../../templates/homepage.mustache"),
+    ),
+    (
+        "index.mustache",
+        include_str!("../../templates/index.mustache"),
+    ),
+    (
+        "taxonomy.mustache",
+        include_str!("../../templates/taxonomy.mustache"),
+    ),
+    (
+        "taxonomy_term.mustache",
+        include_str!("../../templates/taxonomy_term.mustache"),
+    ),
+];
@@ -0,0 +1,323 @@
+//! rss.xml and atom.xml for the whole site and for each section, newest pages first
+
+use chrono::NaiveDateTime;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use super::build_error::BuildError;
+use super::parse_md::ContentFileMetadata;
+use super::path_utils::url_path;
+use super::section_listing::{sort_content_f_metadata, SortBy};
+use super::BuildContext;
+use crate::markdown_parsing::parse_markdown;
+use crate::proj_config_utils::Config;
+
+/// How many chars of the page a summary gets when there's no description
+const SUMMARY_CHARS: usize = 300;
+
+/// A page as it's listed in a feed
+struct FeedItem<'a> {
+    f_metadata: &'a ContentFileMetadata,
+    /// Absolute
+    url: String,
+    /// The page's html, or a summary of it
+    content: String,
+}
+
+/// Writes rss.xml and atom.xml to the output dir's root (every page) and to each section's dir (it's own pages)
+pub fn build_feeds(
+    build_context: &BuildContext,
+    content_f_metadata_vec: &mut [ContentFileMetadata],
+) -> Result<(), BuildError> {
+    let config = build_context.config;
+    if !config.feeds.enabled {
+        return Ok(());
+    }
+    sort_content_f_metadata(content_f_metadata_vec, SortBy::Date);
+    let mut sections: BTreeMap<&Path, Vec<&ContentFileMetadata>> = BTreeMap::new();
+    for f_metadata in content_f_metadata_vec.iter() {
+        let section_path = f_metadata.path.parent().unwrap_or(Path::new(""));
+        sections.entry(section_path).or_default().push(f_metadata);
+    }
+    // the root's feeds have every page
+    sections.insert(Path::new(""), content_f_metadata_vec.iter().collect());
+
+    for (section_path, f_metadata_vec) in sections {
+        let items = f_metadata_vec
+            .into_iter()
+            .take(config.feeds.limit)
+            .map(|f_metadata| feed_item(config, f_metadata))
+            .collect::<Result<Vec<FeedItem>, BuildError>>()?;
+        let feed_dir = build_context.output_dir.join(section_path);
+        fs::create_dir_all(&feed_dir).map_err(|e| BuildError::io(&feed_dir, e))?;
+        let feed_url = |file_name: &str| absolute_url(config, &section_path.join(file_name));
+        let cache = &build_context.cache;
+        cache.write_output(
+            &feed_dir.join("rss.xml"),
+            &rss_feed(config, &items, &feed_url("rss.xml"), build_context.now),
+        )?;
+        cache.write_output(
+            &feed_dir.join("atom.xml"),
+            &atom_feed(config, &items, &feed_url("atom.xml"), build_context.now),
+        )?;
+    }
+    Ok(())
+}
+
+fn feed_item<'a>(
+    config: &Config,
+    f_metadata: &'a ContentFileMetadata,
+) -> Result<FeedItem<'a>, BuildError> {
+    let frontmatter = &f_metadata.frontmatter;
+    let content = match (&frontmatter.description, config.feeds.full_content) {
+        (Some(description), false) => escape_xml(description),
+        (_, full_content) => {
+            let md_str = fs::read_to_string(&f_metadata.source_path)
+                .map_err(|e| BuildError::io(&f_metadata.source_path, e))?;
+            let html = parse_markdown(&md_str);
+            if full_content {
+                html
+            } else {
+                escape_xml(&summary(&html))
+            }
+        }
+    };
+    Ok(FeedItem {
+        f_metadata,
+        url: absolute_url(config, &f_metadata.path),
+        content,
+    })
+}
+
+/// base_url + path (with forward slashes whatever the os)
+pub fn absolute_url(config: &Config, path: &Path) -> String {
+    let base_url = config.base_url.as_deref().unwrap_or_default();
+    format!("{base_url}/{}", url_path(path))
+}
+
+/// The page's text without it's tags, cut down to SUMMARY_CHARS
+fn summary(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let text = words.join(" ");
+    if text.chars().count() <= SUMMARY_CHARS {
+        return text;
+    }
+    let mut summary: String = text.chars().take(SUMMARY_CHARS).collect();
+    summary = summary.trim_end().to_string();
+    summary.push('…');
+    summary
+}
+
+fn rss_feed(config: &Config, items: &[FeedItem], feed_url: &str, now: NaiveDateTime) -> String {
+    let mut rss = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    rss.push_str("<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\">\n<channel>\n");
+    let site_url = absolute_url(config, Path::new(""));
+    let description = config.description.as_deref().unwrap_or(&config.title);
+    // writing to a string can't fail
+    let _ = writeln!(rss, "<title>{}</title>", escape_xml(&config.title));
+    let _ = writeln!(rss, "<link>{}</link>", escape_xml(&site_url));
+    let _ = writeln!(
+        rss,
+        "<description>{}</description>",
+        escape_xml(description)
+    );
+    let _ = writeln!(rss, "<language>{}</language>", escape_xml(&config.language));
+    let _ = writeln!(
+        rss,
+        "<atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\"/>",
+        escape_xml(feed_url)
+    );
+    let _ = writeln!(
+        rss,
+        "<lastBuildDate>{}</lastBuildDate>",
+        now.and_utc().to_rfc2822()
+    );
+    for item in items {
+        rss.push_str("<item>\n");
+        let _ = writeln!(rss, "<title>{}</title>", escape_xml(&item.f_metadata.title));
+        let _ = writeln!(rss, "<link>{}</link>", escape_xml(&item.url));
+        let _ = writeln!(rss, "<guid>{}</guid>", escape_xml(&item.url));
+        if let Some(date) = item.f_metadata.frontmatter.date {
+            let _ = writeln!(rss, "<pubDate>{}</pubDate>", date.and_utc().to_rfc2822());
+        }
+        if let Some(author) = &config.author {
+            let _ = writeln!(rss, "<author>{}</author>", escape_xml(author));
+        }
+        let _ = writeln!(
+            rss,
+            "<description>{}</description>",
+            escape_xml(&item.content)
+        );
+        rss.push_str("</item>\n");
+    }
+    rss.push_str("</channel>\n</rss>\n");
+    rss
+}
+
+fn atom_feed(config: &Config, items: &[FeedItem], feed_url: &str, now: NaiveDateTime) -> String {
+    let mut atom = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        atom,
+        "<feed xmlns=\"http://www.w3.org/2005/Atom\" xml:lang=\"{}\">",
+        escape_xml(&config.language)
+    );
+    let site_url = absolute_url(config, Path::new(""));
+    // the feed was last updated by it's newest page
+    let updated = items
+        .iter()
+        .filter_map(|item| item.f_metadata.frontmatter.date)
+        .max()
+        .unwrap_or(now);
+    let _ = writeln!(atom, "<title>{}</title>", escape_xml(&config.title));
+    if let Some(description) = &config.description {
+        let _ = writeln!(atom, "<subtitle>{}</subtitle>", escape_xml(description));
+    }
+    let _ = writeln!(atom, "<link href=\"{}\"/>", escape_xml(&site_url));
+    let _ = writeln!(
+        atom,
+        "<link href=\"{}\" rel=\"self\"/>",
+        escape_xml(feed_url)
+    );
+    let _ = writeln!(atom, "<id>{}</id>", escape_xml(feed_url));
+    let _ = writeln!(atom, "<updated>{}</updated>", rfc3339(updated));
+    if let Some(author) = &config.author {
+        let _ = writeln!(atom, "<author><name>{}</name></author>", escape_xml(author));
+    }
+    let content_tag = if config.feeds.full_content {
+        "content"
+    } else {
+        "summary"
+    };
+    for item in items {
+        atom.push_str("<entry>\n");
+        let _ = writeln!(
+            atom,
+            "<title>{}</title>",
+            escape_xml(&item.f_metadata.title)
+        );
+        let _ = writeln!(atom, "<link href=\"{}\"/>", escape_xml(&item.url));
+        let _ = writeln!(atom, "<id>{}</id>", escape_xml(&item.url));
+        let date = item.f_metadata.frontmatter.date;
+        let _ = writeln!(
+            atom,
+            "<updated>{}</updated>",
+            rfc3339(date.unwrap_or(updated))
+        );
+        if let Some(date) = date {
+            let _ = writeln!(atom, "<published>{}</published>", rfc3339(date));
+        }
+        let _ = writeln!(
+            atom,
+            "<{content_tag} type=\"html\">{}</{content_tag}>",
+            escape_xml(&item.content)
+        );
+        atom.push_str("</entry>\n");
+    }
+    atom.push_str("</feed>\n");
+    atom
+}
+
+/// Frontmatter dates don't have a timezone, they're taken as utc
+pub fn rfc3339(date: NaiveDateTime) -> String {
+    date.and_utc().to_rfc3339()
+}
+
+pub fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::parse_md::MdContentFileFrontmatter;
+    use crate::proj_config_utils::parse_config;
+    use chrono::NaiveDate;
+    use std::path::PathBuf;
+
+    #[test]
+    pub fn feeds_link_pages_absolutely() {
+        let (config, _) = parse_config(
+            "title: Crabs & co\nbase_url: https://example.com/\nfeeds:\n  enabled: true",
+        )
+        .unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let f_metadata = ContentFileMetadata {
+            title: "First <post>".to_string(),
+            path: PathBuf::from("posts/first"),
+            frontmatter: MdContentFileFrontmatter {
+                date: Some(date),
+                ..Default::default()
+            },
+            extra: BTreeMap::new(),
+            taxonomies: BTreeMap::new(),
+            prev: None,
+            next: None,
+            prev_in_site: None,
+            next_in_site: None,
+            source_path: PathBuf::from("first.md"),
+        };
+        let items = [FeedItem {
+            url: absolute_url(&config, &f_metadata.path),
+            f_metadata: &f_metadata,
+            content: "<p>Hi</p>".to_string(),
+        }];
+        let feed_url = absolute_url(&config, Path::new("posts/rss.xml"));
+        assert_eq!(feed_url, "https://example.com/posts/rss.xml");
+
+        let rss = rss_feed(&config, &items, &feed_url, date);
+        assert!(rss.contains("<title>Crabs &amp; co</title>"), "{rss}");
+        assert!(rss.contains("<link>https://example.com/posts/first</link>"));
+        assert!(rss.contains("<title>First &lt;post&gt;</title>"));
+        assert!(rss.contains("<pubDate>Wed, 1 May 2024 00:00:00 +0000</pubDate>"));
+        assert!(rss.contains("<description>&lt;p&gt;Hi&lt;/p&gt;</description>"));
+
+        let atom = atom_feed(&config, &items, &feed_url, date);
+        assert!(
+            atom.contains("<link href=\"https://example.com/posts/first\"/>"),
+            "{atom}"
+        );
+        assert!(atom.contains("<updated>2024-05-01T00:00:00+00:00</updated>"));
+        assert!(atom.contains("<summary type=\"html\">&lt;p&gt;Hi&lt;/p&gt;</summary>"));
+    }
+
+    #[test]
+    pub fn summaries_are_plain_text_cut_short() {
+        assert_eq!(
+            summary("<h1>Crabs</h1>\n<p>are <em>great</em></p>"),
+            "Crabs are great"
+        );
+        let long = format!("<p>{}</p>", "crab ".repeat(100));
+        let long_summary = summary(&long);
+        assert!(long_summary.ends_with("crab…"));
+        assert!(long_summary.chars().count() <= SUMMARY_CHARS + 1);
+    }
+}
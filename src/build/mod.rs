@@ -1,35 +1,457 @@
+use build_cache::{template_hashes, BuildCache, CACHE_PATH};
+pub use build_error::{BuildError, BuildReport};
 use build_md_file::build_md_file;
-use std::{
-    fs::{self, remove_dir_all},
-    vec,
-};
-use tera::Tera;
+use chrono::{Local, NaiveDateTime};
+use feeds::build_feeds;
+use page_renderer::{load_page_renderer, PageRenderer, PageStream};
+use parse_md::ContentFileMetadata;
+use site::Site;
+use sitemap::build_sitemap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::thread;
+use taxonomies::build_taxonomies;
 use walk_content_dir::walk_content_dir;
 use walk_static_dir::{copy_static_file, walk_static_dir};
 
-use crate::proj_config_utils::get_project_dir;
+use crate::proj_config_utils::{read_config, Config, DirsConfig};
+use crate::threads::map_in_parallel;
 
+mod build_cache;
+mod build_error;
 mod build_md_file;
+pub mod default_templates;
+mod feeds;
+mod page_renderer;
 mod parse_md;
 mod path_utils;
+mod section_listing;
+mod site;
+mod sitemap;
+mod taxonomies;
 mod walk_content_dir;
 mod walk_static_dir;
 
+/// How to build, from the cli
+#[derive(Clone, Debug, Default)]
+pub struct BuildOptions {
+    /// Build into this dir instead of larvae.yaml's dirs.output (relative to the project dir unless it's absolute)
+    pub output: Option<PathBuf>,
+    /// Remove everything the last build wrote first, then build every page
+    pub clean: bool,
+    /// Build pages with draft: true
+    pub drafts: bool,
+    /// Build pages with a publish_date that hasn't come yet
+    pub future: bool,
+    /// Render every page, not just the ones that changed since the last build
+    pub force: bool,
+}
+
+/// What every file in the build needs, resolved once before walking the project
+pub struct BuildContext<'a> {
+    pub options: BuildOptions,
+    /// When the build started, pages are published (or expire) relative to it
+    pub now: NaiveDateTime,
+    pub content_dir: PathBuf,
+    pub output_dir: PathBuf,
+    pub static_dir: PathBuf,
+    pub config: &'a Config,
+    pub page_renderer: &'a dyn PageRenderer,
+    /// What the last build wrote, pages that haven't changed since aren't rendered again
+    pub cache: BuildCache,
+    /// Only this page is rendered, into it's writer instead of the output dir (see render_page)
+    pub page_stream: Option<PageStream<'a>>,
+}
+
 /// Run through md files in content and generate html from them!
-pub fn build(empty_out_dir: bool) {
-    let proj_dir = get_project_dir();
-    let mut tera = match Tera::new("../templates/**/*.html") {
-        Ok(t) => t,
-        Err(e) => {
-            panic!("Parsing erro(s): {}", e);
-        }
+/// a page that fails doesn't stop the build, the report lists every one that did (with why)
+/// only what stops every page from building (IE: larvae.yaml or a template that doesn't parse) is an error
+pub fn build(proj_dir: &Path, options: BuildOptions) -> Result<BuildReport, BuildError> {
+    let (proj_config, warnings) = read_config(proj_dir)?;
+    for warning in &warnings {
+        eprintln!("Warning: {warning}");
+    }
+    let dirs = &proj_config.dirs;
+    let templates_dir = proj_dir.join(&dirs.templates);
+    let now = Local::now().naive_local();
+    let content_dir = proj_dir.join(&dirs.content);
+    let LoadedSite {
+        page_renderer,
+        site,
+        mut failures,
+    } = load_site(proj_dir, &proj_config, &options, now)?;
+    let site_fingerprint = site
+        .fingerprint()
+        .map_err(|e| BuildError::io(&content_dir, e))?;
+    let output_dir = output_dir(proj_dir, dirs, &options);
+    let mut cache = BuildCache::load(
+        proj_dir,
+        &output_dir,
+        template_hashes(
+            &templates_dir,
+            &proj_config.template_engine,
+            site_fingerprint,
+        )
+        .map_err(|e| BuildError::io(&templates_dir, e))?,
+        options.force,
+    );
+    // files that weren't written by megalopa are left alone
+    if options.clean || proj_config.build.clean {
+        failures.extend(cache.clean());
+    }
+    fs::create_dir_all(&output_dir).map_err(|e| BuildError::io(&output_dir, e))?;
+    let build_context = BuildContext {
+        options,
+        now,
+        content_dir,
+        output_dir,
+        static_dir: proj_dir.join(&dirs.static_files),
+        config: &proj_config,
+        page_renderer: page_renderer.as_ref(),
+        cache,
+        page_stream: None,
     };
-    tera.autoescape_on(vec![]);
-    // clear out stale files
-    if empty_out_dir {
-        remove_dir_all("public").unwrap();
-        fs::create_dir("public").unwrap();
-    }
-    walk_content_dir(&proj_dir.join("content"), &tera, build_md_file).unwrap();
-    walk_static_dir(&std::path::Path::new("../static"), copy_static_file).unwrap();
+    let output_dir = &build_context.output_dir;
+    failures.extend(render_site(&site, &build_context));
+    let mut content_f_metadata_vec: Vec<ContentFileMetadata> = site.pages().cloned().collect();
+    failures.extend(build_taxonomies(
+        &build_context,
+        &mut content_f_metadata_vec,
+    ));
+    failures.extend(build_feeds(&build_context, &mut content_f_metadata_vec).err());
+    failures.extend(build_sitemap(&build_context, &site, &content_f_metadata_vec).err());
+    let static_dir = &build_context.static_dir;
+    failures.extend(walk_static_dir(static_dir, &|entry| {
+        copy_static_file(static_dir, output_dir, entry, &build_context.cache)
+    }));
+    let cache = &build_context.cache;
+    let (built, unchanged) = cache.counts();
+    // pages that failed keep what they were last built as
+    if failures.is_empty() {
+        failures.extend(cache.remove_stale());
+    } else {
+        cache.keep_stale();
+    }
+    cache
+        .save(proj_dir)
+        .map_err(|e| BuildError::io(&proj_dir.join(CACHE_PATH), e))?;
+    Ok(BuildReport {
+        built,
+        unchanged,
+        failures,
+    })
+}
+
+/// --output, or larvae.yaml's dirs.output (relative to the project dir unless it's absolute)
+pub fn output_dir(proj_dir: &Path, dirs: &DirsConfig, options: &BuildOptions) -> PathBuf {
+    proj_dir.join(options.output.as_deref().unwrap_or(Path::new(&dirs.output)))
+}
+
+/// Renders the md file that's built to build_file_path (IE: public/posts/first.html) straight into out
+/// for the dev server, which streams pages into the connection as they render (nothing's written to the output dir)
+/// Ok(false) when no md file is built to it (IE: it's a static file, a feed or a taxonomy page)
+pub fn render_page(
+    proj_dir: &Path,
+    options: BuildOptions,
+    build_file_path: &Path,
+    out: &mut (dyn Write + Send),
+) -> Result<bool, BuildError> {
+    let (proj_config, _) = read_config(proj_dir)?;
+    let dirs = &proj_config.dirs;
+    let now = Local::now().naive_local();
+    let LoadedSite {
+        page_renderer,
+        site,
+        ..
+    } = load_site(proj_dir, &proj_config, &options, now)?;
+    let output_dir = output_dir(proj_dir, dirs, &options);
+    let build_context = BuildContext {
+        options,
+        now,
+        content_dir: proj_dir.join(&dirs.content),
+        output_dir,
+        static_dir: proj_dir.join(&dirs.static_files),
+        config: &proj_config,
+        page_renderer: page_renderer.as_ref(),
+        cache: BuildCache::default(),
+        page_stream: Some(PageStream::new(build_file_path, out)),
+    };
+    // the other pages' failures don't matter here
+    for (section, f_path) in site.md_files() {
+        let built = build_md_file(
+            f_path.to_path_buf(),
+            &build_context,
+            &section.pages,
+            &section.subsections,
+            &section.frontmatter,
+        );
+        if build_context
+            .page_stream
+            .as_ref()
+            .is_some_and(PageStream::is_found)
+        {
+            return built.map(|_| true);
+        }
+    }
+    Ok(false)
+}
+
+/// What building (or rendering a page) starts from
+struct LoadedSite {
+    /// With the site registered
+    page_renderer: Box<dyn PageRenderer>,
+    site: Site,
+    /// Pages (or sections) that couldn't be read
+    failures: Vec<BuildError>,
+}
+
+/// every page is collected before any are rendered, so templates can look up any of them
+fn load_site(
+    proj_dir: &Path,
+    proj_config: &Config,
+    options: &BuildOptions,
+    now: NaiveDateTime,
+) -> Result<LoadedSite, BuildError> {
+    let dirs = &proj_config.dirs;
+    let mut page_renderer = load_page_renderer(
+        &proj_dir.join(&dirs.templates),
+        &proj_config.template_engine,
+        proj_config.strict_templates,
+    )?;
+    let content_dir = proj_dir.join(&dirs.content);
+    let (site, failures) = walk_content_dir(&content_dir, proj_config, options, now);
+    page_renderer
+        .register_site(&site)
+        .map_err(|e| BuildError::io(&content_dir, e))?;
+    Ok(LoadedSite {
+        page_renderer,
+        site,
+        failures,
+    })
+}
+
+/// Renders every md file in the site across a thread per core
+/// every page is tried, the ones that failed are returned (in the site's order)
+fn render_site(site: &Site, build_context: &BuildContext) -> Vec<BuildError> {
+    let md_files = site.md_files();
+    let threads = thread::available_parallelism().map_or(1, usize::from);
+    let results = map_in_parallel(threads, &md_files, |(section, f_path)| {
+        build_md_file(
+            f_path.to_path_buf(),
+            build_context,
+            &section.pages,
+            &section.subsections,
+            &section.frontmatter,
+        )
+    });
+    results
+        .into_iter()
+        .filter_map(|result| result.err())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn failing_pages_dont_stop_the_build() {
+        let dir = tempfile::tempdir().unwrap();
+        let proj_dir = dir.path();
+        let content_dir = proj_dir.join("content");
+        fs::create_dir_all(content_dir.join("posts")).unwrap();
+        fs::create_dir_all(content_dir.join("notes")).unwrap();
+        fs::write(proj_dir.join("larvae.yaml"), "title: Site").unwrap();
+        fs::write(content_dir.join("index.md"), "# Home\n").unwrap();
+        fs::write(content_dir.join("posts/good.md"), "# Good\n").unwrap();
+        fs::write(
+            content_dir.join("posts/bad.md"),
+            "---\ndate: soon\n---\nBad\n",
+        )
+        .unwrap();
+        fs::write(
+            content_dir.join("posts/missing.md"),
+            "---\nlayout: missing\n---\nMissing\n",
+        )
+        .unwrap();
+        // sections don't need an index.md
+        fs::write(content_dir.join("notes/note.md"), "# Note\n").unwrap();
+
+        let report = build(proj_dir, BuildOptions::default()).unwrap();
+        assert!(!report.is_success());
+        let mut failed: Vec<&Path> = report.failures.iter().map(BuildError::path).collect();
+        failed.sort();
+        assert_eq!(
+            failed,
+            [
+                content_dir.join("posts/bad.md"),
+                content_dir.join("posts/missing.md")
+            ]
+        );
+        assert!(matches!(report.failures[0], BuildError::Frontmatter { .. }));
+        let output_dir = proj_dir.join("public");
+        assert!(output_dir.join("index.html").is_file());
+        assert!(output_dir.join("posts/good.html").is_file());
+        assert!(output_dir.join("notes/note.html").is_file());
+    }
+
+    #[test]
+    pub fn stale_outputs_are_removed_but_not_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let proj_dir = dir.path();
+        let content_dir = proj_dir.join("content");
+        fs::create_dir_all(content_dir.join("posts")).unwrap();
+        fs::write(proj_dir.join("larvae.yaml"), "title: Site").unwrap();
+        fs::write(content_dir.join("index.md"), "# Home\n").unwrap();
+        fs::write(content_dir.join("posts/kept.md"), "# Kept\n").unwrap();
+        fs::write(content_dir.join("posts/deleted.md"), "# Deleted\n").unwrap();
+        let output_dir = proj_dir.join("dist");
+        let options = BuildOptions {
+            output: Some(output_dir.clone()),
+            ..Default::default()
+        };
+
+        let report = build(proj_dir, options.clone()).unwrap();
+        assert_eq!((report.built, report.unchanged), (3, 0));
+        assert!(!proj_dir.join("public").exists());
+        fs::write(output_dir.join("CNAME"), "example.com").unwrap();
+        fs::remove_file(content_dir.join("posts/deleted.md")).unwrap();
+        let report = build(proj_dir, options.clone()).unwrap();
+        assert!(report.is_success());
+        assert!(!output_dir.join("posts/deleted.html").exists());
+        assert!(output_dir.join("posts/kept.html").is_file());
+        assert!(output_dir.join("CNAME").is_file());
+
+        // a dir left empty goes too
+        fs::remove_file(content_dir.join("posts/kept.md")).unwrap();
+        build(proj_dir, options.clone()).unwrap();
+        assert!(!output_dir.join("posts").exists());
+
+        let clean = BuildOptions {
+            clean: true,
+            ..options
+        };
+        let report = build(proj_dir, clean).unwrap();
+        assert_eq!((report.built, report.unchanged), (1, 0));
+        assert!(output_dir.join("CNAME").is_file());
+    }
+
+    #[test]
+    pub fn pages_can_be_streamed_without_building() {
+        let dir = tempfile::tempdir().unwrap();
+        let proj_dir = dir.path();
+        let content_dir = proj_dir.join("content");
+        fs::create_dir_all(content_dir.join("posts")).unwrap();
+        fs::write(proj_dir.join("larvae.yaml"), "title: Site").unwrap();
+        fs::write(content_dir.join("index.md"), "# Home\n").unwrap();
+        fs::write(content_dir.join("posts/first.md"), "First post\n").unwrap();
+        let output_dir = proj_dir.join("public");
+
+        let mut out = vec![];
+        let build_file_path = output_dir.join("posts/first.html");
+        let is_found = render_page(
+            proj_dir,
+            BuildOptions::default(),
+            &build_file_path,
+            &mut out,
+        );
+        assert!(is_found.unwrap());
+        assert!(String::from_utf8(out).unwrap().contains("First post"));
+        assert!(!output_dir.exists());
+
+        let mut out = vec![];
+        let build_file_path = output_dir.join("posts/missing.html");
+        let is_found = render_page(
+            proj_dir,
+            BuildOptions::default(),
+            &build_file_path,
+            &mut out,
+        );
+        assert!(!is_found.unwrap());
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    pub fn static_files_replace_generated_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let proj_dir = dir.path();
+        fs::create_dir_all(proj_dir.join("content")).unwrap();
+        fs::create_dir_all(proj_dir.join("static")).unwrap();
+        fs::write(
+            proj_dir.join("larvae.yaml"),
+            "title: Site\nbase_url: https://example.com\nsitemap:\n  robots_txt: true",
+        )
+        .unwrap();
+        fs::write(proj_dir.join("content/index.md"), "# Home\n").unwrap();
+        fs::write(proj_dir.join("static/robots.txt"), "Disallow: /secret\n").unwrap();
+
+        // the second build has the first's cache
+        for _ in 0..2 {
+            assert!(build(proj_dir, BuildOptions::default())
+                .unwrap()
+                .is_success());
+            let robots_txt = fs::read_to_string(proj_dir.join("public/robots.txt")).unwrap();
+            assert_eq!(robots_txt, "Disallow: /secret\n");
+        }
+    }
+
+    #[test]
+    pub fn pages_written_to_the_same_place_are_left_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let proj_dir = dir.path();
+        let content_dir = proj_dir.join("content");
+        fs::create_dir_all(content_dir.join("posts/page/2")).unwrap();
+        fs::create_dir_all(content_dir.join("tags")).unwrap();
+        fs::write(
+            proj_dir.join("larvae.yaml"),
+            "title: Site\ntaxonomies:\n  - name: tags",
+        )
+        .unwrap();
+        fs::write(
+            content_dir.join("posts/index.md"),
+            "---\npaginate_by: 1\n---\n",
+        )
+        .unwrap();
+        fs::write(content_dir.join("posts/a.md"), "---\nslug: same\n---\nA\n").unwrap();
+        fs::write(content_dir.join("posts/b.md"), "---\nslug: same\n---\nB\n").unwrap();
+        fs::write(
+            content_dir.join("posts/c.md"),
+            "---\ntags: [rust]\n---\nC\n",
+        )
+        .unwrap();
+        fs::write(content_dir.join("posts/d.md"), "# D\n").unwrap();
+        // posts/page/2/index.html is the index's second page
+        fs::write(content_dir.join("posts/page/2/index.md"), "# Two\n").unwrap();
+        // tags/index.html is the taxonomy's
+        fs::write(content_dir.join("tags/index.md"), "# Tags\n").unwrap();
+
+        let report = build(proj_dir, BuildOptions::default()).unwrap();
+        let collisions: Vec<String> = report.failures.iter().map(|e| e.to_string()).collect();
+        let posts = content_dir.join("posts");
+        assert_eq!(
+            collisions,
+            [
+                format!(
+                    "More than one page would be written to posts/page/2/index.html\n{}, {}",
+                    posts.join("index.md").display(),
+                    posts.join("page/2/index.md").display()
+                ),
+                format!(
+                    "More than one page would be written to posts/same.html\n{}, {}",
+                    posts.join("a.md").display(),
+                    posts.join("b.md").display()
+                ),
+                format!(
+                    "More than one page would be written to tags/index.html\n{}, the tags taxonomy",
+                    content_dir.join("tags/index.md").display()
+                ),
+            ]
+        );
+        let output_dir = proj_dir.join("public");
+        assert!(!output_dir.join("posts/same.html").exists());
+        assert!(!output_dir.join("posts/index.html").exists());
+        assert!(output_dir.join("posts/c.html").is_file());
+        assert!(output_dir.join("tags/rust/index.html").is_file());
+    }
 }
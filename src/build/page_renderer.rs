@@ -0,0 +1,642 @@
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    error::Error,
+    ffi::OsString,
+    fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    sync::{Arc, Mutex},
+};
+use tera::Tera;
+
+use super::build_error::BuildError;
+use super::default_templates::DEFAULT_TEMPLATES;
+use super::parse_md::{ContentFileMetadata, IndexFileMetadata};
+use super::path_utils::url_path;
+use super::section_listing::Paginator;
+use super::site::{Section, Site};
+use super::taxonomies::{Taxonomy, TermListing};
+use crate::{
+    html_templating::{CtxValue, TemplateEngine, UnresolvedName},
+    proj_config_utils::{Config, TemplateEngineKind},
+};
+
+/// Everything a page template gets, whichever engine renders it
+#[derive(Serialize)]
+pub struct PageContext<'a> {
+    pub content: &'a str,
+    pub title: &'a str,
+    /// larvae.yaml
+    pub site: &'a Config,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_title: Option<&'a str>,
+    /// Content pages only, with the page's frontmatter
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<&'a ContentFileMetadata>,
+    /// Index pages only, the section's pages (on this page of it)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_metadata_vec: Option<&'a [ContentFileMetadata]>,
+    /// Index pages only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paginator: Option<&'a Paginator<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir_metadata_vec: Option<&'a [IndexFileMetadata]>,
+    /// Taxonomy pages only (and on their term pages)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub taxonomy: Option<&'a Taxonomy<'a>>,
+    /// Taxonomy term pages only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub term: Option<&'a TermListing<'a>>,
+}
+
+/// Anything the build should warn about once the page is rendered
+pub type RenderResult = Result<Vec<UnresolvedName>, Box<dyn Error>>;
+
+/// Renders a page template by name ("homepage", "index" or "content")
+/// pages are rendered across threads, so it's shared between them
+pub trait PageRenderer: Sync {
+    /// Whether there's a template to render pages with by that name (IE: for a frontmatter layout)
+    fn has_template(&self, template_name: &str) -> bool;
+
+    /// Streams the page into writer
+    fn render_page(
+        &self,
+        template_name: &str,
+        context: &PageContext,
+        writer: &mut dyn Write,
+    ) -> RenderResult;
+
+    /// Lets templates look up any section or page in the site by it's path
+    /// IE: tera's get_section(path="posts") or mustache's {{#get_section posts}}...{{/get_section}}
+    fn register_site(&mut self, site: &Site) -> io::Result<()>;
+}
+
+/// Each section and page in the site by it's path, turned into the engine's values once up front
+struct SiteValues<V> {
+    sections: HashMap<String, V>,
+    pages: HashMap<String, V>,
+    /// Every page, newest first
+    pages_by_date: Vec<V>,
+}
+
+impl<V> SiteValues<V> {
+    fn new<E: Error + Send + Sync + 'static>(
+        site: &Site,
+        section_value: impl Fn(&Section) -> Result<V, E>,
+        page_value: impl Fn(&ContentFileMetadata) -> Result<V, E>,
+    ) -> io::Result<SiteValues<V>> {
+        let mut sections = HashMap::new();
+        for section in &site.sections {
+            let value = section_value(section).map_err(io::Error::other)?;
+            sections.insert(url_path(&section.path), value);
+        }
+        let mut pages = HashMap::new();
+        let mut pages_by_date = vec![];
+        for page in site.pages_by_date() {
+            let value = page_value(page).map_err(io::Error::other)?;
+            pages.insert(url_path(&page.path), value);
+            pages_by_date.push(page_value(page).map_err(io::Error::other)?);
+        }
+        Ok(SiteValues {
+            sections,
+            pages,
+            pages_by_date,
+        })
+    }
+
+    /// The newest limit pages (all of them if there's no limit)
+    fn recent_pages(&self, limit: Option<usize>) -> &[V] {
+        let limit = limit.unwrap_or(self.pages_by_date.len());
+        &self.pages_by_date[..limit.min(self.pages_by_date.len())]
+    }
+}
+
+/// The path argument of a tera get_section/get_page call
+fn path_arg<'a>(args: &'a HashMap<String, tera::Value>, function: &str) -> tera::Result<&'a str> {
+    let path = args
+        .get("path")
+        .and_then(tera::Value::as_str)
+        .ok_or_else(|| {
+            tera::Error::msg(format!(
+                "{function} needs a path IE: {function}(path=\"posts\")"
+            ))
+        })?;
+    Ok(path.trim_matches('/'))
+}
+
+impl PageRenderer for Tera {
+    fn has_template(&self, template_name: &str) -> bool {
+        let file_name = format!("{template_name}.html");
+        self.get_template_names().any(|name| name == file_name)
+    }
+
+    fn render_page(
+        &self,
+        template_name: &str,
+        context: &PageContext,
+        writer: &mut dyn Write,
+    ) -> RenderResult {
+        let tera_context = tera::Context::from_serialize(context)?;
+        self.render_to(&format!("{template_name}.html"), &tera_context, writer)?;
+        Ok(vec![])
+    }
+
+    fn register_site(&mut self, site: &Site) -> io::Result<()> {
+        let values = Arc::new(SiteValues::new(
+            site,
+            |section| tera::to_value(section),
+            |page| tera::to_value(page),
+        )?);
+        let site_values = Arc::clone(&values);
+        self.register_function("get_section", move |args: &HashMap<String, tera::Value>| {
+            let path = path_arg(args, "get_section")?;
+            site_values.sections.get(path).cloned().ok_or_else(|| {
+                tera::Error::msg(format!("get_section: there's no section at \"{path}\""))
+            })
+        });
+        let page_values = Arc::clone(&values);
+        self.register_function("get_page", move |args: &HashMap<String, tera::Value>| {
+            let path = path_arg(args, "get_page")?;
+            page_values
+                .pages
+                .get(path)
+                .cloned()
+                .ok_or_else(|| tera::Error::msg(format!("get_page: there's no page at \"{path}\"")))
+        });
+        self.register_function("get_pages", move |args: &HashMap<String, tera::Value>| {
+            let limit = match args.get("limit") {
+                Some(limit) => Some(limit.as_u64().ok_or_else(|| {
+                    tera::Error::msg("get_pages: limit has to be a number IE: get_pages(limit=5)")
+                })? as usize),
+                None => None,
+            };
+            Ok(tera::Value::Array(values.recent_pages(limit).to_vec()))
+        });
+        Ok(())
+    }
+}
+
+impl PageRenderer for TemplateEngine {
+    fn has_template(&self, template_name: &str) -> bool {
+        TemplateEngine::has_template(self, template_name)
+    }
+
+    fn render_page(
+        &self,
+        template_name: &str,
+        context: &PageContext,
+        writer: &mut dyn Write,
+    ) -> RenderResult {
+        let ctx = CtxValue::from_serialize(context)?;
+        Ok(self.render_to(template_name, &ctx, writer)?)
+    }
+
+    fn register_site(&mut self, site: &Site) -> io::Result<()> {
+        let values = Arc::new(SiteValues::new(
+            site,
+            CtxValue::from_serialize,
+            CtxValue::from_serialize,
+        )?);
+        let site_values = Arc::clone(&values);
+        // a path with nothing at it is an unresolved name (a warning, or an error in strict mode)
+        self.register_data_helper("get_section", move |path| {
+            site_values.sections.get(path.trim_matches('/')).cloned()
+        });
+        let page_values = Arc::clone(&values);
+        self.register_data_helper("get_page", move |path| {
+            page_values.pages.get(path.trim_matches('/')).cloned()
+        });
+        // a limit that isn't a number is unresolved too
+        self.register_data_helper("get_pages", move |limit| {
+            let limit = match limit {
+                "" => None,
+                limit => Some(limit.parse().ok()?),
+            };
+            let pages = values.recent_pages(limit).to_vec();
+            Some(CtxValue::List(Box::new(pages)))
+        });
+        Ok(())
+    }
+}
+
+/// Streams the page into it's file, printing any warnings about the template
+/// (source is what the page is built from, for messages)
+/// it's rendered into a temp file next to it that's only moved into place once it has rendered,
+/// so a page that fails doesn't leave half (or a wrong) page behind
+pub fn render_to_file(
+    page_renderer: &dyn PageRenderer,
+    template_name: &str,
+    context: &PageContext,
+    build_file_path: &Path,
+    source: &Path,
+) -> Result<(), BuildError> {
+    let to_build_error = |e| BuildError::io(build_file_path, e);
+    if let Some(parent) = build_file_path.parent() {
+        fs::create_dir_all(parent).map_err(to_build_error)?;
+    }
+    let mut temp_file_name = OsString::from(".");
+    temp_file_name.push(build_file_path.file_name().unwrap_or_default());
+    temp_file_name.push(".tmp");
+    let temp_path = build_file_path.with_file_name(temp_file_name);
+    let rendered = fs::File::create(&temp_path)
+        .map_err(to_build_error)
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            let warnings = page_renderer
+                .render_page(template_name, context, &mut writer)
+                .map_err(|e| BuildError::render(source, e))?;
+            writer.flush().map_err(to_build_error)?;
+            Ok(warnings)
+        })
+        .and_then(|warnings| {
+            fs::rename(&temp_path, build_file_path).map_err(to_build_error)?;
+            Ok(warnings)
+        });
+    let warnings = match rendered {
+        Ok(warnings) => warnings,
+        Err(e) => {
+            // it might not have been created
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+    };
+    for warning in &warnings {
+        eprintln!("Warning while rendering {}\n{warning}", source.display());
+    }
+    Ok(())
+}
+
+/// A page rendered straight into a writer instead of it's file (IE: the dev server's connection)
+/// every other page is skipped
+pub struct PageStream<'a> {
+    /// The file the page would be built to
+    pub build_file_path: PathBuf,
+    out: Mutex<&'a mut (dyn Write + Send)>,
+    is_found: AtomicBool,
+}
+
+impl<'a> PageStream<'a> {
+    pub fn new(build_file_path: &Path, out: &'a mut (dyn Write + Send)) -> PageStream<'a> {
+        PageStream {
+            build_file_path: build_file_path.to_path_buf(),
+            out: Mutex::new(out),
+            is_found: AtomicBool::new(false),
+        }
+    }
+
+    /// Streams the page into out, printing any warnings about the template
+    pub fn render(
+        &self,
+        page_renderer: &dyn PageRenderer,
+        template_name: &str,
+        context: &PageContext,
+        source: &Path,
+    ) -> Result<(), BuildError> {
+        self.is_found.store(true, Ordering::Relaxed);
+        let mut out = self.out.lock().unwrap();
+        let warnings = page_renderer
+            .render_page(template_name, context, &mut **out)
+            .map_err(|e| BuildError::render(source, e))?;
+        for warning in &warnings {
+            eprintln!("Warning while rendering {}\n{warning}", source.display());
+        }
+        Ok(())
+    }
+
+    /// Whether an md file was built to build_file_path
+    pub fn is_found(&self) -> bool {
+        self.is_found.load(Ordering::Relaxed)
+    }
+}
+
+/// Loads the built in templates into the engine picked in larvae.yaml,
+/// with any file in the project's templates dir (optional) overriding the default of the same name
+/// strict only applies to mustache, tera always errors on missing variables
+pub fn load_page_renderer(
+    templates_dir: &Path,
+    template_engine: &TemplateEngineKind,
+    strict: bool,
+) -> Result<Box<dyn PageRenderer>, BuildError> {
+    let invalid_templates = |message: String| BuildError::Templates {
+        path: templates_dir.to_path_buf(),
+        message,
+    };
+    match template_engine {
+        TemplateEngineKind::Tera => {
+            let to_build_error = |e| invalid_templates(format!("Parsing error(s): {e:?}"));
+            let mut tera = if templates_dir.is_dir() {
+                let glob = templates_dir.join("**/*.html");
+                // parse doesn't check for parents, they might be defaults
+                Tera::parse(&glob.to_string_lossy()).map_err(to_build_error)?
+            } else {
+                Tera::default()
+            };
+            let overridden: Vec<&str> = tera.get_template_names().collect();
+            let defaults: Vec<(&str, &str)> = DEFAULT_TEMPLATES
+                .into_iter()
+                .filter(|(name, _)| name.ends_with(".html") && !overridden.contains(name))
+                .collect();
+            tera.add_raw_templates(defaults).map_err(to_build_error)?;
+            tera.autoescape_on(vec![]);
+            Ok(Box::new(tera))
+        }
+        TemplateEngineKind::Mustache => {
+            let mut engine = TemplateEngine::new();
+            engine.set_strict(strict);
+            for (file_name, source) in DEFAULT_TEMPLATES {
+                if let Some(name) = file_name.strip_suffix(".mustache") {
+                    engine
+                        .add_template(name, source.to_string())
+                        .map_err(|e| invalid_templates(e.to_string()))?;
+                }
+            }
+            if templates_dir.is_dir() {
+                engine
+                    .add_templates_dir(templates_dir)
+                    .map_err(|e| invalid_templates(e.to_string()))?;
+            }
+            Ok(Box::new(engine))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::parse_md::parse_f_metadata_from_md;
+    use crate::proj_config_utils::parse_config;
+
+    #[test]
+    pub fn engines_render_the_same_context() {
+        let mut tera = Tera::default();
+        tera.add_raw_template(
+            "index.html",
+            "{{ title }} ({{ site.language }}):{% for m in file_metadata_vec %} {{ m.title }} {{ m.extra.mood }} {{ m.date }}{% endfor %}",
+        )
+        .unwrap();
+        let mut engine = TemplateEngine::new();
+        engine
+            .add_template(
+                "index",
+                "{{ title }} ({{ site.language }}):{{#file_metadata_vec}} {{ title }} {{ extra.mood }} {{ date }}{{/file_metadata_vec}}".to_string(),
+            )
+            .unwrap();
+
+        let md_file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(md_file.path(), "---\ndate: 2024-05-01\nmood: happy\n---\n").unwrap();
+        let mut metadata = parse_f_metadata_from_md(
+            &md_file.path().to_path_buf(),
+            md_file.path().parent().unwrap(),
+        )
+        .unwrap();
+        metadata.title = "Post".to_string();
+        let file_metadata_vec = vec![metadata];
+        let (site, _) = parse_config("title: Site").unwrap();
+        let context = PageContext {
+            content: "",
+            title: "Site",
+            site: &site,
+            content_title: None,
+            page: None,
+            file_metadata_vec: Some(&file_metadata_vec),
+            paginator: None,
+            taxonomy: None,
+            term: None,
+            dir_metadata_vec: None,
+        };
+        let mut tera_result = vec![];
+        tera.render_page("index", &context, &mut tera_result)
+            .unwrap();
+        let mut mustache_result = vec![];
+        engine
+            .render_page("index", &context, &mut mustache_result)
+            .unwrap();
+        assert_eq!(tera_result, b"Site (en): Post happy 2024-05-01T00:00:00");
+        assert_eq!(tera_result, mustache_result);
+    }
+
+    #[test]
+    pub fn project_templates_override_the_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("content.html"),
+            "{% extends \"base.html\" %}{% block content %}custom {{ content }}{% endblock content %}",
+        )
+        .unwrap();
+        fs::write(dir.path().join("content.mustache"), "custom {{ content }}").unwrap();
+        let (site, _) = parse_config("title: Site").unwrap();
+        let context = PageContext {
+            content: "page",
+            title: "Site",
+            site: &site,
+            content_title: Some("Post"),
+            page: None,
+            file_metadata_vec: None,
+            paginator: None,
+            taxonomy: None,
+            term: None,
+            dir_metadata_vec: None,
+        };
+
+        for template_engine in [TemplateEngineKind::Tera, TemplateEngineKind::Mustache] {
+            let page_renderer = load_page_renderer(dir.path(), &template_engine, false).unwrap();
+            assert!(page_renderer.has_template("content"));
+            assert!(!page_renderer.has_template("post"));
+            let mut overridden = vec![];
+            page_renderer
+                .render_page("content", &context, &mut overridden)
+                .unwrap();
+            let overridden = String::from_utf8(overridden).unwrap();
+            assert!(overridden.contains("custom page"), "{overridden}");
+
+            let default_renderer =
+                load_page_renderer(&dir.path().join("missing"), &template_engine, false).unwrap();
+            let mut default = vec![];
+            default_renderer
+                .render_page("content", &context, &mut default)
+                .unwrap();
+            let default = String::from_utf8(default).unwrap();
+            assert!(default.contains("<title>Post - Site</title>"), "{default}");
+            assert!(!default.contains("custom"));
+        }
+    }
+
+    #[test]
+    pub fn templates_look_up_sections_and_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        let content_dir = dir.path().join("content");
+        fs::create_dir_all(content_dir.join("posts")).unwrap();
+        fs::write(content_dir.join("posts/index.md"), "Posts\n").unwrap();
+        fs::write(
+            content_dir.join("posts/first.md"),
+            "---\ndate: 2024-05-01\n---\nFirst\n",
+        )
+        .unwrap();
+        fs::write(
+            content_dir.join("posts/second.md"),
+            "---\ndate: 2024-06-01\n---\nSecond\n",
+        )
+        .unwrap();
+        let (site_config, _) = parse_config("title: Site").unwrap();
+        let (site, failures) = crate::build::walk_content_dir::walk_content_dir(
+            &content_dir,
+            &site_config,
+            &Default::default(),
+            chrono::Local::now().naive_local(),
+        );
+        assert!(failures.is_empty());
+        let templates_dir = dir.path().join("templates");
+        fs::create_dir(&templates_dir).unwrap();
+        fs::write(
+            templates_dir.join("homepage.html"),
+            "{% set posts = get_section(path=\"posts\") %}{{ posts.title }}:{% for p in posts.pages %} {{ p.title }}{% endfor %} {% set first = get_page(path=\"/posts/first\") %}{{ first.prev.title }}",
+        )
+        .unwrap();
+        fs::write(
+            templates_dir.join("homepage.mustache"),
+            "{{#get_section posts}}{{ title }}:{{#pages}} {{ title }}{{/pages}}{{/get_section}} {{#get_page /posts/first}}{{ prev.title }}{{/get_page}}",
+        )
+        .unwrap();
+        let context = PageContext {
+            content: "",
+            title: "Site",
+            site: &site_config,
+            content_title: None,
+            page: None,
+            file_metadata_vec: None,
+            paginator: None,
+            taxonomy: None,
+            term: None,
+            dir_metadata_vec: None,
+        };
+
+        for template_engine in [TemplateEngineKind::Tera, TemplateEngineKind::Mustache] {
+            let mut page_renderer =
+                load_page_renderer(&templates_dir, &template_engine, true).unwrap();
+            page_renderer.register_site(&site).unwrap();
+            let mut result = vec![];
+            page_renderer
+                .render_page("homepage", &context, &mut result)
+                .unwrap();
+            // newest first, so the first post comes after the second
+            assert_eq!(
+                String::from_utf8(result).unwrap(),
+                "posts: second first second"
+            );
+        }
+    }
+
+    #[test]
+    pub fn templates_list_recent_pages_across_sections() {
+        let dir = tempfile::tempdir().unwrap();
+        let content_dir = dir.path().join("content");
+        fs::create_dir_all(content_dir.join("posts")).unwrap();
+        fs::create_dir_all(content_dir.join("notes")).unwrap();
+        for (path, date) in [
+            ("posts/first.md", "2024-05-01"),
+            ("notes/note.md", "2024-05-15"),
+            ("posts/second.md", "2024-06-01"),
+        ] {
+            fs::write(content_dir.join(path), format!("---\ndate: {date}\n---\n")).unwrap();
+        }
+        let (site_config, _) = parse_config("title: Site").unwrap();
+        let (site, failures) = crate::build::walk_content_dir::walk_content_dir(
+            &content_dir,
+            &site_config,
+            &Default::default(),
+            chrono::Local::now().naive_local(),
+        );
+        assert!(failures.is_empty());
+        let templates_dir = dir.path().join("templates");
+        fs::create_dir(&templates_dir).unwrap();
+        fs::write(
+            templates_dir.join("homepage.html"),
+            "{% for p in get_pages(limit=2) %}{{ p.title }} {% endfor %}{% set note = get_page(path=\"notes/note\") %}{{ note.prev_in_site.title }} {{ note.next_in_site.title }} {{ get_pages() | length }}",
+        )
+        .unwrap();
+        fs::write(
+            templates_dir.join("homepage.mustache"),
+            "{{#get_pages 2}}{{ title }} {{/get_pages}}{{#get_page notes/note}}{{ prev_in_site.title }} {{ next_in_site.title }}{{/get_page}} {{#get_pages}}.{{/get_pages}}",
+        )
+        .unwrap();
+        let context = PageContext {
+            content: "",
+            title: "Site",
+            site: &site_config,
+            content_title: None,
+            page: None,
+            file_metadata_vec: None,
+            paginator: None,
+            taxonomy: None,
+            term: None,
+            dir_metadata_vec: None,
+        };
+
+        let mut results = vec![];
+        for template_engine in [TemplateEngineKind::Tera, TemplateEngineKind::Mustache] {
+            let mut page_renderer =
+                load_page_renderer(&templates_dir, &template_engine, true).unwrap();
+            page_renderer.register_site(&site).unwrap();
+            let mut result = vec![];
+            page_renderer
+                .render_page("homepage", &context, &mut result)
+                .unwrap();
+            results.push(String::from_utf8(result).unwrap());
+        }
+        // newest first whatever section they're in
+        assert_eq!(
+            results,
+            ["second note second first 3", "second note second first ..."]
+        );
+    }
+
+    #[test]
+    pub fn failed_renders_leave_the_page_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let templates_dir = dir.path().join("templates");
+        fs::create_dir(&templates_dir).unwrap();
+        fs::write(
+            templates_dir.join("homepage.mustache"),
+            "<h1>{{title}}</h1>{{nope}}<p>after</p>",
+        )
+        .unwrap();
+        let (site_config, _) = parse_config("title: Site").unwrap();
+        let context = PageContext {
+            content: "",
+            title: "Site",
+            site: &site_config,
+            content_title: None,
+            page: None,
+            file_metadata_vec: None,
+            paginator: None,
+            taxonomy: None,
+            term: None,
+            dir_metadata_vec: None,
+        };
+        let page_renderer =
+            load_page_renderer(&templates_dir, &TemplateEngineKind::Mustache, true).unwrap();
+        let build_file_path = dir.path().join("public/index.html");
+
+        let render = || {
+            render_to_file(
+                page_renderer.as_ref(),
+                "homepage",
+                &context,
+                &build_file_path,
+                Path::new("content/index.md"),
+            )
+        };
+        assert!(render().is_err());
+        assert!(!build_file_path.exists());
+        fs::write(&build_file_path, "last build's").unwrap();
+        assert!(render().is_err());
+        assert_eq!(
+            fs::read_to_string(&build_file_path).unwrap(),
+            "last build's"
+        );
+        // nor it's temp file
+        assert_eq!(fs::read_dir(dir.path().join("public")).unwrap().count(), 1);
+    }
+}
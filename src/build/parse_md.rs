@@ -1,78 +1,231 @@
-use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_yaml;
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::{self, PathBuf};
+use std::io;
+use std::path::{Path, PathBuf};
 
+use crate::html_templating::helpers::parse_date;
 use crate::markdown_parsing::parse_frontmatter;
 
+use super::build_error::BuildError;
 use super::path_utils::get_relative_file_path_for_routing;
+use super::section_listing::SortBy;
+use super::site::PageLink;
+use super::taxonomies::TaxonomyTerm;
+use super::BuildOptions;
 
-#[derive(Debug, Serialize)]
+/// A content page, templates get it as page (and index pages get a list of them)
+#[derive(Clone, Debug, Serialize)]
 pub struct ContentFileMetadata {
     pub title: String,
+    /// Where the page is served from, relative to the site's root with no extension
     pub path: PathBuf,
+    #[serde(flatten)]
+    pub frontmatter: MdContentFileFrontmatter,
+    /// Every frontmatter field, including the ones we don't know about
+    pub extra: BTreeMap<String, serde_yaml::Value>,
+    /// The page's terms for each taxonomy in larvae.yaml IE: taxonomies.tags
+    pub taxonomies: BTreeMap<String, Vec<TaxonomyTerm>>,
+    /// The pages before and after it in it's section's order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev: Option<PageLink>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<PageLink>,
+    /// The pages before and after it in the whole site, newest first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev_in_site: Option<PageLink>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_in_site: Option<PageLink>,
+    /// The md file the page is built from
+    #[serde(skip)]
+    pub source_path: PathBuf,
 }
-#[derive(Debug, Deserialize)]
+
+/// The frontmatter fields the build knows about, all optional
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct MdContentFileFrontmatter {
+    /// Serialized as the page's title (which falls back to the file name)
+    #[serde(skip_serializing)]
     pub title: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_date")]
+    pub date: Option<NaiveDateTime>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Drafts are only built with --drafts (and by the dev server)
+    #[serde(default)]
+    pub draft: bool,
+    /// The page isn't built before this date (unless --future)
+    #[serde(default, deserialize_with = "deserialize_date")]
+    pub publish_date: Option<NaiveDateTime>,
+    /// The page isn't built from this date on
+    #[serde(default, deserialize_with = "deserialize_date")]
+    pub expiry_date: Option<NaiveDateTime>,
+    /// Lower weights sort first
+    pub weight: Option<i64>,
+    /// The template to render the page with instead of content
+    pub layout: Option<String>,
+    /// Replaces the file name in the page's path
+    pub slug: Option<String>,
+    /// false leaves the page out of sitemap.xml
+    pub sitemap: Option<bool>,
+}
+
+impl ContentFileMetadata {
+    /// Whether the page goes into the build, drafts, scheduled and expired pages are left out
+    pub fn is_published(&self, options: &BuildOptions, now: NaiveDateTime) -> bool {
+        let frontmatter = &self.frontmatter;
+        let is_scheduled = frontmatter.publish_date.is_some_and(|date| date > now);
+        let is_expired = frontmatter.expiry_date.is_some_and(|date| date <= now);
+        (options.drafts || !frontmatter.draft) && (options.future || !is_scheduled) && !is_expired
+    }
 }
-/// Parses frontmatter as Yaml for the given generic schema
+
+/// Dates are strings in any format parse_date understands
+fn deserialize_date<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<NaiveDateTime>, D::Error> {
+    let Some(date_str) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    parse_date(&date_str).map(Some).ok_or_else(|| {
+        serde::de::Error::custom(format!(
+            "date: \"{date_str}\" isn't a date IE: 2024-05-01 or 2024-05-01T10:30:00"
+        ))
+    })
+}
+/// Parses frontmatter as Yaml for the given generic schema (None if there's no frontmatter)
 pub fn parse_frontmatter_from_md<T: for<'a> serde::Deserialize<'a>>(
     md_str: &str,
-) -> Result<T, serde_yaml::Error> {
-    let o_frontmatter = parse_frontmatter(md_str);
-    if let Some(frontmatter) = o_frontmatter {
-        serde_yaml::from_str::<T>(&frontmatter)
-    } else {
-        serde_yaml::from_str("")
+) -> Result<Option<T>, serde_yaml::Error> {
+    match parse_frontmatter(md_str) {
+        Some(frontmatter) if !frontmatter.trim().is_empty() => {
+            serde_yaml::from_str::<T>(&frontmatter).map(Some)
+        }
+        _ => Ok(None),
     }
 }
 
 /// defaults to the file name as a title if one isn't found
-pub fn parse_f_metadata_from_md(f_path: &PathBuf) -> ContentFileMetadata {
-    let md_str = fs::read_to_string(&f_path).unwrap();
-    // try to parse from frontmatter
-    if let Ok(frontmatter) = parse_frontmatter_from_md::<MdContentFileFrontmatter>(&md_str) {
-        if let Some(file_title) = frontmatter.title {
-            return ContentFileMetadata {
-                title: file_title,
-                path: get_relative_file_path_for_routing(f_path, "content"),
-            };
+/// errors on frontmatter that isn't yaml or has a known field of the wrong type
+pub fn parse_f_metadata_from_md(
+    f_path: &PathBuf,
+    content_dir: &Path,
+) -> Result<ContentFileMetadata, BuildError> {
+    let md_str = fs::read_to_string(f_path).map_err(|e| BuildError::io(f_path, e))?;
+    let invalid_frontmatter = |e: serde_yaml::Error| BuildError::frontmatter(f_path, e);
+    // parsed once, the known fields and extra (every field) are both taken from it
+    let (frontmatter, extra) = match parse_frontmatter_from_md::<serde_yaml::Value>(&md_str)
+        .map_err(invalid_frontmatter)?
+    {
+        Some(value) => (
+            serde_yaml::from_value::<MdContentFileFrontmatter>(value.clone())
+                .map_err(invalid_frontmatter)?,
+            serde_yaml::from_value::<BTreeMap<String, serde_yaml::Value>>(value)
+                .map_err(invalid_frontmatter)?,
+        ),
+        None => Default::default(),
+    };
+    if let Some(slug) = &frontmatter.slug {
+        if slug.is_empty() || slug::slugify(slug) != *slug {
+            return Err(BuildError::frontmatter(
+                f_path,
+                format!("slug: \"{slug}\" has to be lowercase words joined by dashes IE: my-post"),
+            ));
         }
     }
-    // default to the filename
-    return ContentFileMetadata {
-        title: String::from(f_path.file_stem().unwrap().to_str().unwrap()),
-        path: get_relative_file_path_for_routing(f_path, "content"),
-    };
+
+    let mut path = get_relative_file_path_for_routing(f_path, content_dir)
+        .map_err(|e| BuildError::io(f_path, e))?;
+    if let Some(slug) = &frontmatter.slug {
+        path.set_file_name(slug);
+    }
+    Ok(ContentFileMetadata {
+        // default to the filename
+        title: frontmatter.title.clone().unwrap_or_else(|| {
+            f_path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        }),
+        path,
+        frontmatter,
+        extra,
+        // filled in by the content walk, which knows the taxonomies
+        taxonomies: BTreeMap::new(),
+        // filled in once the section's pages are sorted (and once every section's are, for the site)
+        prev: None,
+        next: None,
+        prev_in_site: None,
+        next_in_site: None,
+        source_path: f_path.clone(),
+    })
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 pub struct IndexFileMetadata {
     pub content_name: String,
     pub path: PathBuf,
 }
-#[derive(Deserialize)]
+/// A section's index.md frontmatter
+#[derive(Debug, Default, Deserialize)]
 pub struct MdIndexFileFrontmatter {
     pub content_title: Option<String>,
+    /// The template to render the index page with instead of index (or homepage)
+    pub layout: Option<String>,
+    /// The template for the section's content pages that don't have a layout of their own
+    pub page_layout: Option<String>,
+    /// How the index page orders the section's pages
+    #[serde(default)]
+    pub sort_by: SortBy,
+    /// How many pages the index page lists before splitting into page/2/ etc. (all of them by default)
+    pub paginate_by: Option<usize>,
+}
+
+/// Parses the frontmatter of the index.md in dir (which is optional)
+pub fn parse_index_frontmatter(dir_path: &Path) -> Result<MdIndexFileFrontmatter, BuildError> {
+    let index_f_path = dir_path.join("index.md");
+    if !index_f_path.is_file() {
+        return Ok(MdIndexFileFrontmatter::default());
+    }
+    let md_str = fs::read_to_string(&index_f_path).map_err(|e| BuildError::io(&index_f_path, e))?;
+    let frontmatter = parse_frontmatter_from_md::<MdIndexFileFrontmatter>(&md_str)
+        .map_err(|e| BuildError::frontmatter(&index_f_path, e))?
+        .unwrap_or_default();
+    if frontmatter.paginate_by == Some(0) {
+        return Err(BuildError::frontmatter(
+            &index_f_path,
+            "paginate_by has to be at least 1",
+        ));
+    }
+    Ok(frontmatter)
 }
 /// parses content name from dir - is this in index.md frontmatter? if it is it needs a different variable name...
-pub fn parse_index_f_metadata(dir_path: &path::PathBuf) -> IndexFileMetadata {
+/// the dir's name when it doesn't have an index.md (or it's frontmatter doesn't parse, that's reported when the dir's built)
+pub fn parse_index_f_metadata(
+    dir_path: &Path,
+    content_dir: &Path,
+) -> io::Result<IndexFileMetadata> {
     // we currently store dir metadata on the index.md under the var "content_name" in the frontmatter
-    let index_f_path = dir_path.join("index.md");
-    let md_str = fs::read_to_string(index_f_path).expect("Couldn't find an index.md in dir");
-    if let Ok(frontmatter) = parse_frontmatter_from_md::<MdIndexFileFrontmatter>(&md_str) {
-        if let Some(content_name) = frontmatter.content_title {
-            return IndexFileMetadata {
-                content_name,
-                path: get_relative_file_path_for_routing(&dir_path, "content"),
-            };
-        };
-    };
-    return IndexFileMetadata {
-        content_name: String::from(dir_path.file_stem().unwrap().to_str().unwrap()),
-        path: get_relative_file_path_for_routing(&dir_path, "content"),
-    };
+    let path = get_relative_file_path_for_routing(dir_path, content_dir)?;
+    let content_title = fs::read_to_string(dir_path.join("index.md"))
+        .ok()
+        .and_then(|md_str| parse_frontmatter_from_md::<MdIndexFileFrontmatter>(&md_str).ok())
+        .flatten()
+        .and_then(|frontmatter| frontmatter.content_title);
+    Ok(IndexFileMetadata {
+        content_name: content_title.unwrap_or_else(|| {
+            dir_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        }),
+        path,
+    })
 }
 
 #[cfg(test)]
@@ -82,7 +235,80 @@ mod tests {
     #[test]
     pub fn md_parse() {
         let file_str = "---\ntitle: Hello World\ntest: true\n---\n\n# heading 1\n## heading 2\n\ncontent content woo\n";
-        let frontmatter = parse_frontmatter_from_md::<MdContentFileFrontmatter>(&file_str).unwrap();
+        let frontmatter = parse_frontmatter_from_md::<MdContentFileFrontmatter>(file_str)
+            .unwrap()
+            .unwrap();
         assert_eq!(frontmatter.title.unwrap(), "Hello World")
     }
+
+    #[test]
+    pub fn content_metadata_from_frontmatter() {
+        let dir = tempfile::tempdir().unwrap();
+        let f_path = dir.path().join("posts/first.md");
+        fs::create_dir(dir.path().join("posts")).unwrap();
+        fs::write(
+            &f_path,
+            "---\ndate: 2024-05-01\ntags: [crabs, rust]\nweight: 2\nslug: hello-world\nsubtitle: hi\n---\n# First",
+        )
+        .unwrap();
+        let metadata = parse_f_metadata_from_md(&f_path, dir.path()).unwrap();
+        assert_eq!(metadata.title, "first");
+        assert_eq!(metadata.path, PathBuf::from("posts/hello-world"));
+        assert_eq!(metadata.frontmatter.date, parse_date("2024-05-01"));
+        assert_eq!(metadata.frontmatter.tags, vec!["crabs", "rust"]);
+        assert_eq!(metadata.frontmatter.weight, Some(2));
+        assert!(!metadata.frontmatter.draft);
+        assert_eq!(metadata.extra["subtitle"], "hi");
+        assert_eq!(metadata.extra["weight"], 2);
+
+        fs::write(&f_path, "---\ndate: tomorrow\n---\n").unwrap();
+        let err = parse_f_metadata_from_md(&f_path, dir.path()).unwrap_err();
+        assert!(
+            err.to_string().contains("\"tomorrow\" isn't a date"),
+            "{err}"
+        );
+        fs::write(&f_path, "---\nslug: Hello World\n---\n").unwrap();
+        assert!(parse_f_metadata_from_md(&f_path, dir.path()).is_err());
+        fs::write(&f_path, "# No frontmatter").unwrap();
+        assert!(parse_f_metadata_from_md(&f_path, dir.path()).is_ok());
+    }
+
+    #[test]
+    pub fn drafts_scheduled_and_expired_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        let f_path = dir.path().join("post.md");
+        let now = parse_date("2024-05-01T12:00:00").unwrap();
+        let is_published = |frontmatter: &str, options: &BuildOptions| {
+            fs::write(&f_path, format!("---\n{frontmatter}\n---\n# Post")).unwrap();
+            parse_f_metadata_from_md(&f_path, dir.path())
+                .unwrap()
+                .is_published(options, now)
+        };
+        let drafts = BuildOptions {
+            drafts: true,
+            ..BuildOptions::default()
+        };
+        let future = BuildOptions {
+            future: true,
+            ..BuildOptions::default()
+        };
+
+        assert!(is_published("title: Post", &BuildOptions::default()));
+        assert!(!is_published("draft: true", &BuildOptions::default()));
+        assert!(is_published("draft: true", &drafts));
+        assert!(!is_published(
+            "publish_date: 2024-05-02",
+            &BuildOptions::default()
+        ));
+        assert!(is_published("publish_date: 2024-05-02", &future));
+        assert!(is_published(
+            "publish_date: 2024-05-01",
+            &BuildOptions::default()
+        ));
+        assert!(!is_published("expiry_date: 2024-05-01", &drafts));
+        assert!(is_published(
+            "expiry_date: 2024-05-02",
+            &BuildOptions::default()
+        ));
+    }
 }
@@ -1,5 +1,5 @@
-use std::path::{self, PathBuf};
-use std::ffi::OsStr;
+use std::io;
+use std::path::{Path, PathBuf};
 
 /// get an absolute path to public/<relative path from /content>
 // pub fn get_build_path(file_path: &PathBuf, proj_path: &PathBuf) -> PathBuf {
@@ -10,35 +10,61 @@ use std::ffi::OsStr;
 //     absolute_path
 // }
 
-/// given an absolute file path get relative path after provided dir name
-pub fn get_relative_file_path(abs_path: &PathBuf, from: &str) -> PathBuf {
-    let abs_path = abs_path.clone();
-    let mut prefix = PathBuf::new();
-    for component in abs_path.components() {
-        prefix.push(component);
-        if component.eq(&path::Component::Normal(&OsStr::new(from))) {
-            break;
-        }
+/// given an absolute file path get relative path from the provided dir (errors if it isn't inside it)
+pub fn get_relative_file_path(abs_path: &Path, from: &Path) -> io::Result<PathBuf> {
+    match abs_path.strip_prefix(from) {
+        Ok(relative_path) if relative_path != Path::new("") => Ok(relative_path.to_path_buf()),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("isn't inside {}", from.display()),
+        )),
     }
-    assert_ne!(prefix, abs_path);
-
-    abs_path.strip_prefix(prefix).unwrap().to_path_buf()
 }
 
-/// given an absolute path get relative path after provided dir name with no file extension
-pub fn get_relative_file_path_for_routing(content_path_ref: &PathBuf, from: &str) -> PathBuf {
-    let mut relative_path = get_relative_file_path(content_path_ref, from);
+/// given an absolute path get relative path from the provided dir with no file extension
+pub fn get_relative_file_path_for_routing(
+    content_path_ref: &Path,
+    from: &Path,
+) -> io::Result<PathBuf> {
+    let mut relative_path = get_relative_file_path(content_path_ref, from)?;
     relative_path.set_extension("");
-    relative_path
+    Ok(relative_path)
+}
+
+/// a relative path joined with /s whatever the os, IE: posts/first
+/// what templates look pages up with, and what urls (and template names) are made from
+pub fn url_path(path: &Path) -> String {
+    let components: Vec<String> = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+    components.join("/")
 }
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
 
     #[test]
     fn relative_path() {
-        // TODO
+        let content_dir = Path::new("/proj/content");
+        assert_eq!(
+            get_relative_file_path(Path::new("/proj/content/posts/a.md"), content_dir).unwrap(),
+            Path::new("posts/a.md")
+        );
+        assert_eq!(
+            get_relative_file_path_for_routing(Path::new("/proj/content/posts/a.md"), content_dir)
+                .unwrap(),
+            Path::new("posts/a")
+        );
+        // outside the dir (or the dir itself) isn't a file in it
+        assert!(get_relative_file_path(Path::new("/proj/static/a.css"), content_dir).is_err());
+        assert!(get_relative_file_path(content_dir, content_dir).is_err());
+    }
 
+    #[test]
+    fn url_paths() {
+        assert_eq!(url_path(&Path::new("posts").join("first")), "posts/first");
+        assert_eq!(url_path(Path::new("")), "");
     }
 }
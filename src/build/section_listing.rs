@@ -0,0 +1,200 @@
+//! Ordering a section's pages and splitting them into the index page's pages
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+use super::parse_md::ContentFileMetadata;
+use super::path_utils::url_path;
+
+/// How a section's index.md orders the pages it lists (sort_by in it's frontmatter)
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    /// Newest first
+    #[default]
+    Date,
+    /// Lightest first
+    Weight,
+    Title,
+}
+
+/// Pages missing the field being sorted by go last, ties are broken by title then path
+/// so the order never depends on the file system
+pub fn sort_content_f_metadata(
+    content_f_metadata_vec: &mut [ContentFileMetadata],
+    sort_by: SortBy,
+) {
+    content_f_metadata_vec.sort_by(|a, b| compare_content_f_metadata(a, b, sort_by));
+}
+
+/// The order sort_content_f_metadata puts pages in
+pub fn compare_content_f_metadata(
+    a: &ContentFileMetadata,
+    b: &ContentFileMetadata,
+    sort_by: SortBy,
+) -> Ordering {
+    let ordering = match sort_by {
+        SortBy::Date => missing_last(a.frontmatter.date, b.frontmatter.date, |a, b| b.cmp(&a)),
+        SortBy::Weight => {
+            missing_last(a.frontmatter.weight, b.frontmatter.weight, |a, b| a.cmp(&b))
+        }
+        SortBy::Title => Ordering::Equal,
+    };
+    ordering
+        .then_with(|| a.title.cmp(&b.title))
+        .then_with(|| a.path.cmp(&b.path))
+}
+
+fn missing_last<T>(a: Option<T>, b: Option<T>, cmp: impl Fn(T, T) -> Ordering) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => cmp(a, b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// One page of a section's index, templates get it as paginator
+#[derive(Debug, Serialize)]
+pub struct Paginator<'a> {
+    /// 1 based
+    pub current: usize,
+    pub total: usize,
+    /// The pages listed on this page (templates get them as file_metadata_vec)
+    #[serde(skip)]
+    pub pages: &'a [ContentFileMetadata],
+    pub prev_url: Option<String>,
+    pub next_url: Option<String>,
+    /// Where it's written, relative to the output dir
+    #[serde(skip)]
+    pub output_path: PathBuf,
+}
+
+/// Splits a section's (sorted) pages up, paginate_by at a time
+/// the first page is the section's index.html and the rest are at page/<n>/index.html
+pub fn paginate<'a>(
+    content_f_metadata_vec: &'a [ContentFileMetadata],
+    paginate_by: Option<usize>,
+    section_path: &Path,
+) -> Vec<Paginator<'a>> {
+    let chunks: Vec<&[ContentFileMetadata]> = match paginate_by {
+        Some(paginate_by) if !content_f_metadata_vec.is_empty() => {
+            content_f_metadata_vec.chunks(paginate_by.max(1)).collect()
+        }
+        _ => vec![content_f_metadata_vec],
+    };
+    let url = |page_number: usize| {
+        let section_url = match url_path(section_path).as_str() {
+            "" => String::new(),
+            path => format!("/{path}"),
+        };
+        match page_number {
+            1 => format!("{section_url}/"),
+            _ => format!("{section_url}/page/{page_number}/"),
+        }
+    };
+    let total = chunks.len();
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, pages)| {
+            let current = i + 1;
+            let output_path = match current {
+                1 => section_path.join("index.html"),
+                _ => section_path.join(format!("page/{current}/index.html")),
+            };
+            Paginator {
+                current,
+                total,
+                pages,
+                prev_url: (current > 1).then(|| url(current - 1)),
+                next_url: (current < total).then(|| url(current + 1)),
+                output_path,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::parse_md::MdContentFileFrontmatter;
+    use crate::html_templating::helpers::parse_date;
+
+    fn metadata(title: &str, date: Option<&str>, weight: Option<i64>) -> ContentFileMetadata {
+        ContentFileMetadata {
+            title: title.to_string(),
+            path: PathBuf::from("posts").join(title),
+            frontmatter: MdContentFileFrontmatter {
+                date: date.and_then(parse_date),
+                weight,
+                ..MdContentFileFrontmatter::default()
+            },
+            extra: Default::default(),
+            taxonomies: Default::default(),
+            prev: None,
+            next: None,
+            prev_in_site: None,
+            next_in_site: None,
+            source_path: PathBuf::new(),
+        }
+    }
+
+    fn titles(content_f_metadata_vec: &[ContentFileMetadata]) -> Vec<&str> {
+        content_f_metadata_vec
+            .iter()
+            .map(|f_metadata| f_metadata.title.as_str())
+            .collect()
+    }
+
+    #[test]
+    pub fn sorts_by_date_weight_and_title() {
+        let mut posts = vec![
+            metadata("b", None, Some(1)),
+            metadata("c", Some("2024-01-01"), None),
+            metadata("a", None, None),
+            metadata("d", Some("2024-05-01"), Some(2)),
+        ];
+        sort_content_f_metadata(&mut posts, SortBy::Date);
+        assert_eq!(titles(&posts), vec!["d", "c", "a", "b"]);
+        sort_content_f_metadata(&mut posts, SortBy::Weight);
+        assert_eq!(titles(&posts), vec!["b", "d", "a", "c"]);
+        sort_content_f_metadata(&mut posts, SortBy::Title);
+        assert_eq!(titles(&posts), vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    pub fn paginates_into_page_dirs() {
+        let posts: Vec<ContentFileMetadata> = ["a", "b", "c", "d", "e"]
+            .into_iter()
+            .map(|title| metadata(title, None, None))
+            .collect();
+        let paginators = paginate(&posts, Some(2), Path::new("blog/posts"));
+        assert_eq!(paginators.len(), 3);
+        assert_eq!(titles(paginators[1].pages), vec!["c", "d"]);
+        assert_eq!(
+            paginators[0].output_path,
+            Path::new("blog/posts/index.html")
+        );
+        assert_eq!(paginators[0].prev_url, None);
+        assert_eq!(
+            paginators[0].next_url.as_deref(),
+            Some("/blog/posts/page/2/")
+        );
+        assert_eq!(
+            paginators[2].output_path,
+            Path::new("blog/posts/page/3/index.html")
+        );
+        assert_eq!(
+            paginators[2].prev_url.as_deref(),
+            Some("/blog/posts/page/2/")
+        );
+        assert_eq!(paginators[2].next_url, None);
+
+        let paginators = paginate(&posts, None, Path::new("posts"));
+        assert_eq!(paginators.len(), 1);
+        assert_eq!(paginators[0].pages.len(), 5);
+        assert_eq!(paginate(&[], Some(2), Path::new("posts")).len(), 1);
+    }
+}
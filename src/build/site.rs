@@ -0,0 +1,122 @@
+//! Everything in the content dir, collected before any of it's rendered
+//! templates can look any section or page up with get_section and get_page
+
+use fnv::FnvHasher;
+use serde::Serialize;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::PathBuf;
+
+use super::parse_md::{ContentFileMetadata, IndexFileMetadata, MdIndexFileFrontmatter};
+use super::section_listing::{compare_content_f_metadata, SortBy};
+
+/// A dir in the content dir with it's pages, templates get it from get_section
+#[derive(Serialize)]
+pub struct Section {
+    /// The index.md's content_title, or the dir's name (the site's title for the root)
+    pub title: String,
+    /// Relative to the content dir, empty for the root
+    pub path: PathBuf,
+    /// IE: /posts/
+    pub url: String,
+    /// The section's parent's path, None for the root
+    pub parent: Option<PathBuf>,
+    /// Where the section's index page is built from (sections don't have to have one)
+    #[serde(skip)]
+    pub index_f_path: Option<PathBuf>,
+    #[serde(skip)]
+    pub frontmatter: MdIndexFileFrontmatter,
+    /// Published pages only, in the order the section sorts them
+    pub pages: Vec<ContentFileMetadata>,
+    /// The dirs in the section, sorted by path
+    pub subsections: Vec<IndexFileMetadata>,
+}
+
+/// Enough of a page to link to it
+#[derive(Clone, Debug, Serialize)]
+pub struct PageLink {
+    pub title: String,
+    pub path: PathBuf,
+}
+
+/// Every section in the content dir, each before it's subsections
+pub struct Site {
+    pub sections: Vec<Section>,
+}
+
+impl Site {
+    /// Every md file that's built, each section's index.md then it's pages
+    pub fn md_files(&self) -> Vec<(&Section, &PathBuf)> {
+        let mut md_files = vec![];
+        for section in &self.sections {
+            md_files.extend(section.index_f_path.iter().map(|f_path| (section, f_path)));
+            md_files.extend(
+                section
+                    .pages
+                    .iter()
+                    .map(|page| (section, &page.source_path)),
+            );
+        }
+        md_files
+    }
+
+    /// Every published page in the site
+    pub fn pages(&self) -> impl Iterator<Item = &ContentFileMetadata> {
+        self.sections.iter().flat_map(|section| &section.pages)
+    }
+
+    /// Every published page in the site newest first (IE: for recent posts), templates get it from get_pages
+    pub fn pages_by_date(&self) -> Vec<&ContentFileMetadata> {
+        let mut pages: Vec<&ContentFileMetadata> = self.pages().collect();
+        pages.sort_by(|a, b| compare_content_f_metadata(a, b, SortBy::Date));
+        pages
+    }
+
+    /// Changes whenever anything templates can look up does
+    pub fn fingerprint(&self) -> io::Result<u64> {
+        let mut hasher = FnvHasher::default();
+        for section in &self.sections {
+            serde_yaml::to_string(section)
+                .map_err(io::Error::other)?
+                .hash(&mut hasher);
+        }
+        Ok(hasher.finish())
+    }
+}
+
+/// Links each page to the ones before and after it in the whole site, newest first like pages_by_date
+pub fn link_site_pages(sections: &mut [Section]) {
+    let mut pages: Vec<(usize, usize)> = vec![];
+    for (section_i, section) in sections.iter().enumerate() {
+        pages.extend((0..section.pages.len()).map(|page_i| (section_i, page_i)));
+    }
+    let page = |(section_i, page_i): (usize, usize)| &sections[section_i].pages[page_i];
+    pages.sort_by(|a, b| compare_content_f_metadata(page(*a), page(*b), SortBy::Date));
+    let links: Vec<PageLink> = pages
+        .iter()
+        .map(|i| PageLink {
+            title: page(*i).title.clone(),
+            path: page(*i).path.clone(),
+        })
+        .collect();
+    for (i, (section_i, page_i)) in pages.into_iter().enumerate() {
+        let page = &mut sections[section_i].pages[page_i];
+        page.prev_in_site = i.checked_sub(1).map(|prev| links[prev].clone());
+        page.next_in_site = links.get(i + 1).cloned();
+    }
+}
+
+/// Links each page to the ones before and after it (once the section's pages are sorted)
+pub fn link_pages(pages: &mut [ContentFileMetadata]) {
+    let links: Vec<PageLink> = pages
+        .iter()
+        .map(|page| PageLink {
+            title: page.title.clone(),
+            path: page.path.clone(),
+        })
+        .collect();
+    for (i, page) in pages.iter_mut().enumerate() {
+        page.prev = i.checked_sub(1).map(|prev| links[prev].clone());
+        page.next = links.get(i + 1).cloned();
+    }
+}
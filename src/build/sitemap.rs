@@ -0,0 +1,228 @@
+//! sitemap.xml listing every page the build writes, and a robots.txt pointing at it
+
+use chrono::{DateTime, NaiveDateTime, Timelike, Utc};
+use std::fs;
+use std::path::Path;
+
+use super::build_error::BuildError;
+use super::feeds::{absolute_url, escape_xml, rfc3339};
+use super::parse_md::ContentFileMetadata;
+use super::section_listing::paginate;
+use super::site::Site;
+use super::taxonomies::collect_taxonomy;
+use super::BuildContext;
+
+/// A page in the sitemap
+#[derive(Debug, PartialEq)]
+struct SitemapEntry {
+    /// Absolute
+    url: String,
+    lastmod: Option<NaiveDateTime>,
+}
+
+/// Writes sitemap.xml (and robots.txt if it's turned on, and there isn't one in the static dir) to the output dir's root
+/// with content pages (unless they have sitemap: false), index pages (and their pages) and taxonomy pages
+pub fn build_sitemap(
+    build_context: &BuildContext,
+    site: &Site,
+    content_f_metadata_vec: &[ContentFileMetadata],
+) -> Result<(), BuildError> {
+    let config = build_context.config;
+    let Some(base_url) = config.base_url.as_deref() else {
+        return Ok(());
+    };
+    if !config.sitemap.enabled {
+        return Ok(());
+    }
+    let mut entries = vec![];
+    for f_metadata in content_f_metadata_vec {
+        if f_metadata.frontmatter.sitemap == Some(false) {
+            continue;
+        }
+        entries.push(SitemapEntry {
+            url: absolute_url(config, &f_metadata.path),
+            lastmod: page_lastmod(f_metadata)?,
+        });
+    }
+    // sections without an index.md don't get an index page
+    for section in &site.sections {
+        let Some(index_f_path) = &section.index_f_path else {
+            continue;
+        };
+        let index_lastmod = modified(index_f_path)?;
+        if section.parent.is_none() {
+            entries.push(SitemapEntry {
+                url: format!("{base_url}/"),
+                lastmod: Some(index_lastmod),
+            });
+            continue;
+        }
+        // the section's listing changes with it's pages
+        let mut lastmod = index_lastmod;
+        for f_metadata in &section.pages {
+            lastmod = lastmod.max(page_lastmod(f_metadata)?.unwrap_or(lastmod));
+        }
+        // paginated index pages are wherever paginate puts them (IE: page/<n>/ after the first)
+        let paginators = paginate(
+            &section.pages,
+            section.frontmatter.paginate_by,
+            &section.path,
+        );
+        for paginator in paginators {
+            let page_dir = paginator.output_path.parent().unwrap_or(Path::new(""));
+            entries.push(SitemapEntry {
+                url: format!("{}/", absolute_url(config, page_dir)),
+                lastmod: Some(lastmod),
+            });
+        }
+    }
+    for taxonomy_config in &config.taxonomies {
+        let taxonomy = collect_taxonomy(&taxonomy_config.name, content_f_metadata_vec);
+        let mut taxonomy_lastmod = None;
+        for listing in &taxonomy.terms {
+            let lastmod = listing
+                .pages
+                .iter()
+                .filter_map(|f_metadata| f_metadata.frontmatter.date)
+                .max();
+            taxonomy_lastmod = taxonomy_lastmod.max(lastmod);
+            entries.push(SitemapEntry {
+                url: format!("{base_url}{}", listing.term.url),
+                lastmod,
+            });
+        }
+        entries.push(SitemapEntry {
+            url: format!("{base_url}{}", taxonomy.url),
+            lastmod: taxonomy_lastmod,
+        });
+    }
+    entries.sort_by(|a, b| a.url.cmp(&b.url));
+
+    let output_dir = &build_context.output_dir;
+    let cache = &build_context.cache;
+    cache.write_output(&output_dir.join("sitemap.xml"), &sitemap(&entries))?;
+    if config.sitemap.robots_txt && !build_context.static_dir.join("robots.txt").is_file() {
+        cache.write_output(
+            &output_dir.join("robots.txt"),
+            &format!("User-agent: *\nAllow: /\n\nSitemap: {base_url}/sitemap.xml\n"),
+        )?;
+    }
+    Ok(())
+}
+
+/// The page's date, or when it's file was last changed
+fn page_lastmod(f_metadata: &ContentFileMetadata) -> Result<Option<NaiveDateTime>, BuildError> {
+    match f_metadata.frontmatter.date {
+        Some(date) => Ok(Some(date)),
+        None => modified(&f_metadata.source_path).map(Some),
+    }
+}
+
+/// File times are utc, like frontmatter dates are taken to be
+fn modified(f_path: &Path) -> Result<NaiveDateTime, BuildError> {
+    let modified = fs::metadata(f_path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|e| BuildError::io(f_path, e))?;
+    let modified = DateTime::<Utc>::from(modified).naive_utc();
+    // to the second, like frontmatter dates
+    Ok(modified.with_nanosecond(0).unwrap_or(modified))
+}
+
+fn sitemap(entries: &[SitemapEntry]) -> String {
+    let mut sitemap = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    sitemap.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for entry in entries {
+        sitemap.push_str("<url>\n");
+        sitemap.push_str(&format!("<loc>{}</loc>\n", escape_xml(&entry.url)));
+        if let Some(lastmod) = entry.lastmod {
+            sitemap.push_str(&format!("<lastmod>{}</lastmod>\n", rfc3339(lastmod)));
+        }
+        sitemap.push_str("</url>\n");
+    }
+    sitemap.push_str("</urlset>\n");
+    sitemap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::walk_content_dir::walk_content_dir;
+    use crate::proj_config_utils::parse_config;
+    use chrono::Local;
+    use tera::Tera;
+
+    #[test]
+    pub fn lists_every_page_but_the_left_out_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let content_dir = dir.path().join("content");
+        let output_dir = dir.path().join("public");
+        fs::create_dir_all(content_dir.join("posts")).unwrap();
+        fs::create_dir(&output_dir).unwrap();
+        fs::write(content_dir.join("index.md"), "# Home\n").unwrap();
+        fs::write(
+            content_dir.join("posts/index.md"),
+            "---\npaginate_by: 1\n---\nPosts\n",
+        )
+        .unwrap();
+        fs::write(
+            content_dir.join("posts/first.md"),
+            "---\ndate: 2024-05-01\ntags: [Rust]\n---\nFirst\n",
+        )
+        .unwrap();
+        fs::write(
+            content_dir.join("posts/second.md"),
+            "---\ndate: 2024-06-01\n---\nSecond\n",
+        )
+        .unwrap();
+        fs::write(
+            content_dir.join("posts/hidden.md"),
+            "---\nsitemap: false\n---\nHidden\n",
+        )
+        .unwrap();
+
+        let (config, _) = parse_config(
+            "title: Site\nbase_url: https://example.com\ntaxonomies:\n  - name: tags\nsitemap:\n  robots_txt: true",
+        )
+        .unwrap();
+        let now = Local::now().naive_local();
+        let (site, failures) = walk_content_dir(&content_dir, &config, &Default::default(), now);
+        assert!(failures.is_empty());
+        let content_f_metadata_vec: Vec<ContentFileMetadata> = site.pages().cloned().collect();
+        let build_context = BuildContext {
+            options: Default::default(),
+            now,
+            content_dir,
+            output_dir: output_dir.clone(),
+            static_dir: dir.path().join("static"),
+            config: &config,
+            page_renderer: &Tera::default(),
+            cache: Default::default(),
+            page_stream: None,
+        };
+        build_sitemap(&build_context, &site, &content_f_metadata_vec).unwrap();
+
+        let sitemap = fs::read_to_string(output_dir.join("sitemap.xml")).unwrap();
+        let locs: Vec<&str> = sitemap
+            .lines()
+            .filter_map(|line| line.strip_prefix("<loc>")?.strip_suffix("</loc>"))
+            .collect();
+        assert_eq!(
+            locs,
+            [
+                "https://example.com/",
+                "https://example.com/posts/",
+                "https://example.com/posts/first",
+                "https://example.com/posts/page/2/",
+                "https://example.com/posts/page/3/",
+                "https://example.com/posts/second",
+                "https://example.com/tags/",
+                "https://example.com/tags/rust/",
+            ]
+        );
+        assert!(sitemap.contains(
+            "<loc>https://example.com/posts/first</loc>\n<lastmod>2024-05-01T00:00:00+00:00</lastmod>"
+        ));
+        let robots_txt = fs::read_to_string(output_dir.join("robots.txt")).unwrap();
+        assert!(robots_txt.ends_with("Sitemap: https://example.com/sitemap.xml\n"));
+    }
+}
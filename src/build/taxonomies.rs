@@ -0,0 +1,252 @@
+//! Taxonomies (IE: tags) group pages across sections by the terms in their frontmatter
+//! each gets a page listing it's terms (/tags/) and a page per term listing it's pages (/tags/rust/)
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::build_error::BuildError;
+use super::page_renderer::{render_to_file, PageContext};
+use super::parse_md::ContentFileMetadata;
+use super::section_listing::{sort_content_f_metadata, SortBy};
+use super::BuildContext;
+use crate::proj_config_utils::TaxonomyConfig;
+
+/// A term a page is listed under IE: the rust tag
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TaxonomyTerm {
+    pub name: String,
+    pub slug: String,
+    /// IE: /tags/rust/
+    pub url: String,
+}
+
+/// A taxonomy's listing page, templates get it as taxonomy
+#[derive(Debug, Serialize)]
+pub struct Taxonomy<'a> {
+    pub name: &'a str,
+    pub url: String,
+    /// Sorted by name
+    pub terms: Vec<TermListing<'a>>,
+}
+
+/// A term's listing page, templates get it as term (and each of taxonomy.terms)
+#[derive(Debug, Serialize)]
+pub struct TermListing<'a> {
+    #[serde(flatten)]
+    pub term: TaxonomyTerm,
+    pub page_count: usize,
+    /// Newest first
+    pub pages: Vec<&'a ContentFileMetadata>,
+}
+
+/// The terms a page lists in it's frontmatter for each taxonomy in larvae.yaml
+/// each taxonomy's field has to be a list of strings IE: tags: [rust, crabs]
+pub fn page_terms(
+    f_metadata: &ContentFileMetadata,
+    taxonomies: &[TaxonomyConfig],
+) -> Result<BTreeMap<String, Vec<TaxonomyTerm>>, BuildError> {
+    let mut page_terms = BTreeMap::new();
+    for taxonomy in taxonomies {
+        let Some(value) = f_metadata.extra.get(&taxonomy.name) else {
+            continue;
+        };
+        let names: Option<Vec<&str>> = value
+            .as_sequence()
+            .and_then(|names| names.iter().map(serde_yaml::Value::as_str).collect());
+        let Some(names) = names else {
+            return Err(BuildError::frontmatter(
+                &f_metadata.source_path,
+                format!(
+                    "{} has to be a list of strings IE: {}: [rust, crabs]",
+                    taxonomy.name, taxonomy.name
+                ),
+            ));
+        };
+        let terms = names
+            .into_iter()
+            .map(|name| {
+                let slug = slug::slugify(name);
+                TaxonomyTerm {
+                    name: name.to_string(),
+                    url: format!("/{}/{slug}/", taxonomy.name),
+                    slug,
+                }
+            })
+            .collect();
+        page_terms.insert(taxonomy.name.clone(), terms);
+    }
+    Ok(page_terms)
+}
+
+/// Groups the pages by their terms, terms with the same slug (IE: Rust and rust) are the same term
+pub fn collect_taxonomy<'a>(
+    name: &'a str,
+    content_f_metadata_vec: &'a [ContentFileMetadata],
+) -> Taxonomy<'a> {
+    let mut terms: BTreeMap<&str, TermListing> = BTreeMap::new();
+    for f_metadata in content_f_metadata_vec {
+        for term in f_metadata.taxonomies.get(name).into_iter().flatten() {
+            let listing = terms.entry(&term.slug).or_insert_with(|| TermListing {
+                term: term.clone(),
+                page_count: 0,
+                pages: vec![],
+            });
+            // a page listing the same term twice is only listed once
+            if !listing
+                .pages
+                .iter()
+                .any(|page| std::ptr::eq(*page, f_metadata))
+            {
+                listing.pages.push(f_metadata);
+            }
+        }
+    }
+    let mut terms: Vec<TermListing> = terms.into_values().collect();
+    for listing in &mut terms {
+        listing.page_count = listing.pages.len();
+    }
+    terms.sort_by_key(|listing| listing.term.name.to_lowercase());
+    Taxonomy {
+        name,
+        url: format!("/{name}/"),
+        terms,
+    }
+}
+
+/// Writes <taxonomy>/index.html and <taxonomy>/<term>/index.html for each taxonomy in larvae.yaml
+/// with the taxonomy and taxonomy_term templates, returning the pages that failed
+pub fn build_taxonomies(
+    build_context: &BuildContext,
+    content_f_metadata_vec: &mut [ContentFileMetadata],
+) -> Vec<BuildError> {
+    let mut failures = vec![];
+    sort_content_f_metadata(content_f_metadata_vec, SortBy::Date);
+    for taxonomy_config in &build_context.config.taxonomies {
+        let taxonomy = collect_taxonomy(&taxonomy_config.name, content_f_metadata_vec);
+        let mut context = PageContext {
+            content: "",
+            title: &build_context.config.title,
+            site: build_context.config,
+            content_title: Some(taxonomy.name),
+            page: None,
+            file_metadata_vec: None,
+            paginator: None,
+            dir_metadata_vec: None,
+            taxonomy: Some(&taxonomy),
+            term: None,
+        };
+        let taxonomy_dir = build_context.output_dir.join(taxonomy.name);
+        failures.extend(
+            render_unless_fresh(
+                build_context,
+                "taxonomy",
+                &context,
+                &taxonomy_dir.join("index.html"),
+            )
+            .err(),
+        );
+        for listing in &taxonomy.terms {
+            context.content_title = Some(&listing.term.name);
+            context.term = Some(listing);
+            let path = Path::new(&listing.term.slug).join("index.html");
+            failures.extend(
+                render_unless_fresh(
+                    build_context,
+                    "taxonomy_term",
+                    &context,
+                    &taxonomy_dir.join(path),
+                )
+                .err(),
+            );
+        }
+    }
+    failures
+}
+
+/// Taxonomy pages are made from their context alone, they're only rendered when it (or the template) changes
+fn render_unless_fresh(
+    build_context: &BuildContext,
+    template_name: &str,
+    context: &PageContext,
+    build_file_path: &Path,
+) -> Result<(), BuildError> {
+    let cache = &build_context.cache;
+    let fingerprint = cache
+        .page_fingerprint(template_name, context, &[])
+        .map_err(|e| BuildError::io(build_file_path, e))?;
+    if cache.is_fresh(build_file_path, fingerprint) {
+        return Ok(());
+    }
+    render_to_file(
+        build_context.page_renderer,
+        template_name,
+        context,
+        build_file_path,
+        build_file_path,
+    )?;
+    cache.record(build_file_path, fingerprint);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::parse_md::MdContentFileFrontmatter;
+    use std::path::PathBuf;
+
+    fn metadata(
+        title: &str,
+        frontmatter: &str,
+        taxonomies: &[TaxonomyConfig],
+    ) -> Result<ContentFileMetadata, BuildError> {
+        let mut f_metadata = ContentFileMetadata {
+            title: title.to_string(),
+            path: PathBuf::from(title),
+            frontmatter: MdContentFileFrontmatter::default(),
+            extra: serde_yaml::from_str(frontmatter).unwrap(),
+            taxonomies: BTreeMap::new(),
+            prev: None,
+            next: None,
+            prev_in_site: None,
+            next_in_site: None,
+            source_path: PathBuf::from(format!("{title}.md")),
+        };
+        f_metadata.taxonomies = page_terms(&f_metadata, taxonomies)?;
+        Ok(f_metadata)
+    }
+
+    #[test]
+    pub fn groups_pages_by_term() {
+        let taxonomies = [
+            TaxonomyConfig {
+                name: "tags".to_string(),
+            },
+            TaxonomyConfig {
+                name: "series".to_string(),
+            },
+        ];
+        let pages = vec![
+            metadata("a", "tags: [Rust, crabs]", &taxonomies).unwrap(),
+            metadata("b", "tags: [rust]\nseries: [Intro]", &taxonomies).unwrap(),
+            metadata("c", "title: c", &taxonomies).unwrap(),
+        ];
+        assert_eq!(
+            pages[1].taxonomies["series"],
+            vec![TaxonomyTerm {
+                name: "Intro".to_string(),
+                slug: "intro".to_string(),
+                url: "/series/intro/".to_string()
+            }]
+        );
+
+        let tags = collect_taxonomy("tags", &pages);
+        let terms: Vec<(&str, usize)> = tags
+            .terms
+            .iter()
+            .map(|listing| (listing.term.name.as_str(), listing.page_count))
+            .collect();
+        assert_eq!(terms, vec![("crabs", 1), ("Rust", 2)]);
+        assert!(metadata("d", "tags: rust", &taxonomies).is_err());
+    }
+}
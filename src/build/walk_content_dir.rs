@@ -1,42 +1,226 @@
-use std::{path, io, fs};
-use tera::Tera;
-use super::parse_md::{parse_f_metadata_from_md, parse_index_f_metadata, ContentFileMetadata, IndexFileMetadata};
+use std::collections::{BTreeMap, BTreeSet};
+use std::{path, fs};
+use chrono::NaiveDateTime;
+use super::BuildOptions;
+use super::build_error::BuildError;
+use super::path_utils::url_path;
+use super::section_listing::{paginate, sort_content_f_metadata};
+use super::site::{link_pages, link_site_pages, Section, Site};
+use super::taxonomies::page_terms;
+use super::parse_md::{
+    parse_f_metadata_from_md, parse_index_f_metadata, parse_index_frontmatter, ContentFileMetadata,
+    MdIndexFileFrontmatter,
+};
+use crate::proj_config_utils::Config;
 
-/// recursively walks through the dir and calls cb on files (also parses frontmatter out of md)
+/// walks through the content dir collecting every section with it's pages (and their frontmatter)
+/// nothing's rendered yet, so every page can be rendered knowing about the whole site
+/// pages (or sections) that can't be read are left out of the site and returned as failures
 pub fn walk_content_dir(
-    dir_path: &path::PathBuf,
-    tera: &Tera,
-    cb: fn(path::PathBuf, &Tera, &Vec<ContentFileMetadata>, &Vec<IndexFileMetadata>) -> std::io::Result<()>,
-) -> io::Result<()> {
-    if dir_path.is_dir() {
-        let mut content_f_metadata_vec: Vec<ContentFileMetadata> = vec![];
-        let mut index_f_metadata_vec: Vec<IndexFileMetadata> = vec![];
-        for f_entry in fs::read_dir(&dir_path)? {
-            let f_entry = f_entry?;
-            let f_path = f_entry.path();
-            if f_path.is_file()
-                && f_path.extension().is_some_and(|ext| ext == "md")
-                && f_entry.file_name().ne("index.md")
-            {
-                content_f_metadata_vec.push(parse_f_metadata_from_md(&f_path));
+    content_dir: &path::Path,
+    config: &Config,
+    options: &BuildOptions,
+    now: NaiveDateTime,
+) -> (Site, Vec<BuildError>) {
+    let mut sections = vec![];
+    let mut failures = vec![];
+    let walk = ContentWalk { content_dir, config, options, now };
+    walk.collect_sections(content_dir, None, &mut sections, &mut failures);
+    remove_collisions(&mut sections, config, &mut failures);
+    link_site_pages(&mut sections);
+    (Site { sections }, failures)
+}
+
+/// Leaves out every md file that would be written to the same place as another
+/// (or as a taxonomy page), which one ended up there would depend on which thread finished last
+fn remove_collisions(
+    sections: &mut [Section],
+    config: &Config,
+    failures: &mut Vec<BuildError>,
+) {
+    // output path (relative to the output dir) -> what's written there, md files by their path
+    let mut outputs: BTreeMap<path::PathBuf, Vec<OutputSource>> = BTreeMap::new();
+    for section in sections.iter() {
+        if let Some(index_f_path) = &section.index_f_path {
+            let output_paths = match section.parent {
+                // the homepage isn't paginated
+                None => vec![path::PathBuf::from("index.html")],
+                Some(_) => paginate(
+                    &section.pages,
+                    section.frontmatter.paginate_by,
+                    &section.path,
+                )
+                .into_iter()
+                .map(|paginator| paginator.output_path)
+                .collect(),
+            };
+            for output_path in output_paths {
+                let source = OutputSource::Md(index_f_path.clone());
+                outputs.entry(output_path).or_default().push(source);
             }
+        }
+        for page in &section.pages {
+            let mut output_path = page.path.clone();
+            output_path.set_extension("html");
+            let source = OutputSource::Md(page.source_path.clone());
+            outputs.entry(output_path).or_default().push(source);
+        }
+    }
+    for taxonomy in &config.taxonomies {
+        let taxonomy_dir = path::Path::new(&taxonomy.name);
+        let slugs: BTreeSet<&str> = sections
+            .iter()
+            .flat_map(|section| &section.pages)
+            .flat_map(|page| page.taxonomies.get(&taxonomy.name).into_iter().flatten())
+            .map(|term| term.slug.as_str())
+            .collect();
+        let output_paths = std::iter::once(taxonomy_dir.join("index.html"))
+            .chain(slugs.into_iter().map(|slug| taxonomy_dir.join(slug).join("index.html")));
+        for output_path in output_paths {
+            let source = OutputSource::Taxonomy(taxonomy.name.clone());
+            outputs.entry(output_path).or_default().push(source);
+        }
+    }
+
+    let mut colliding = BTreeSet::new();
+    for (output_path, sources) in outputs {
+        if sources.len() < 2 {
+            continue;
+        }
+        colliding.extend(sources.iter().filter_map(|source| match source {
+            OutputSource::Md(f_path) => Some(f_path.clone()),
+            OutputSource::Taxonomy(_) => None,
+        }));
+        failures.push(BuildError::Collision {
+            output_path,
+            sources: sources.iter().map(OutputSource::to_string).collect(),
+        });
+    }
+    if colliding.is_empty() {
+        return;
+    }
+    for section in sections.iter_mut() {
+        if section.index_f_path.as_ref().is_some_and(|f_path| colliding.contains(f_path)) {
+            section.index_f_path = None;
+        }
+        section.pages.retain(|page| !colliding.contains(&page.source_path));
+        link_pages(&mut section.pages);
+    }
+}
+
+/// Something written to the output dir
+enum OutputSource {
+    Md(path::PathBuf),
+    /// The taxonomy's listing or one of it's term pages
+    Taxonomy(String),
+}
+
+impl std::fmt::Display for OutputSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputSource::Md(f_path) => write!(f, "{}", f_path.display()),
+            OutputSource::Taxonomy(name) => write!(f, "the {name} taxonomy"),
+        }
+    }
+}
+
+/// What deciding which pages are published needs
+struct ContentWalk<'a> {
+    content_dir: &'a path::Path,
+    config: &'a Config,
+    options: &'a BuildOptions,
+    now: NaiveDateTime,
+}
+
+impl ContentWalk<'_> {
+    /// recursively adds the dir's section then it's sub dirs' sections
+    /// a section with invalid index.md frontmatter is left out, it's sub dirs aren't
+    fn collect_sections(
+        &self,
+        dir_path: &path::Path,
+        parent: Option<&path::Path>,
+        sections: &mut Vec<Section>,
+        failures: &mut Vec<BuildError>,
+    ) {
+        if !dir_path.is_dir() {
+            return;
+        }
+        let content_dir = self.content_dir;
+        let section_path = dir_path.strip_prefix(content_dir).unwrap_or(dir_path).to_path_buf();
+        let frontmatter = parse_index_frontmatter(dir_path);
+        let title = match (&frontmatter, parent) {
+            (Ok(MdIndexFileFrontmatter { content_title: Some(content_title), .. }), _) => content_title.clone(),
+            (_, None) => self.config.title.clone(),
+            (_, Some(_)) => dir_path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        };
+        let url = match url_path(&section_path).as_str() {
+            "" => String::from("/"),
+            path => format!("/{path}/"),
+        };
+        let mut section = Section {
+            title,
+            url,
+            parent: parent.map(path::Path::to_path_buf),
+            index_f_path: None,
+            frontmatter: MdIndexFileFrontmatter::default(),
+            pages: vec![],
+            subsections: vec![],
+            path: section_path,
+        };
+        let f_entries = match fs::read_dir(dir_path) {
+            Ok(f_entries) => f_entries,
+            Err(e) => return failures.push(BuildError::io(dir_path, e)),
+        };
+        let mut sub_dirs: Vec<path::PathBuf> = vec![];
+        for f_entry in f_entries {
+            let f_entry = match f_entry {
+                Ok(f_entry) => f_entry,
+                Err(e) => {
+                    failures.push(BuildError::io(dir_path, e));
+                    continue;
+                }
+            };
+            let f_path = f_entry.path();
             if f_path.is_dir() {
-                index_f_metadata_vec.push(parse_index_f_metadata(&f_path))
+                match parse_index_f_metadata(&f_path, content_dir) {
+                    Ok(index_f_metadata) => section.subsections.push(index_f_metadata),
+                    Err(e) => failures.push(BuildError::io(&f_path, e)),
+                }
+                sub_dirs.push(f_path);
+            } else if f_entry.file_name().eq("index.md") {
+                section.index_f_path = Some(f_path);
+            } else if f_path.extension().is_some_and(|ext| ext == "md") {
+                match self.parse_page(&f_path) {
+                    // drafts, scheduled and expired pages aren't built or listed
+                    Ok(f_metadata) if f_metadata.is_published(self.options, self.now) => {
+                        section.pages.push(f_metadata)
+                    }
+                    Ok(_) => {}
+                    Err(e) => failures.push(e),
+                }
             }
         }
-        for f_entry in fs::read_dir(&dir_path)? {
-            let f_entry = f_entry?;
-            if f_entry.path().is_dir() {
-                walk_content_dir(&f_entry.path(), tera, cb)?;
-            } else {
-                cb(
-                    f_entry.path(),
-                    tera,
-                    &content_f_metadata_vec,
-                    &index_f_metadata_vec,
-                )?;
+        section.subsections.sort_by(|a, b| a.path.cmp(&b.path));
+        let section_path = section.path.clone();
+        match frontmatter {
+            Ok(frontmatter) => {
+                sort_content_f_metadata(&mut section.pages, frontmatter.sort_by);
+                link_pages(&mut section.pages);
+                section.frontmatter = frontmatter;
+                sections.push(section);
             }
+            Err(e) => failures.push(e),
         }
+        // the same order whatever order the file system lists them in
+        sub_dirs.sort();
+        for sub_dir in sub_dirs {
+            self.collect_sections(&sub_dir, Some(&section_path), sections, failures);
+        }
+    }
+
+    fn parse_page(&self, f_path: &path::PathBuf) -> Result<ContentFileMetadata, BuildError> {
+        let mut f_metadata = parse_f_metadata_from_md(f_path, self.content_dir)?;
+        f_metadata.taxonomies = page_terms(&f_metadata, &self.config.taxonomies)?;
+        Ok(f_metadata)
     }
-    Ok(())
-}
\ No newline at end of file
+}
@@ -1,30 +1,62 @@
 use std::{fs, fs::DirEntry, io, path::Path};
 
-use crate::{build::path_utils::get_relative_file_path, proj_config_utils::get_project_dir};
+use super::build_cache::{file_fingerprint, BuildCache};
+use super::build_error::BuildError;
+use super::path_utils::get_relative_file_path;
 
 /// recursively walks through the dir and calls cb on files
-pub fn walk_static_dir(dir: &Path, cb: fn(&DirEntry) -> io::Result<()>) -> io::Result<()> {
-    if dir.is_dir() {
-        for entry in fs::read_dir(dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_dir() {
-                walk_static_dir(&path, cb)?;
-            } else {
-                cb(&entry)?;
+/// a file (or dir) that fails doesn't stop the rest, the failures are returned
+pub fn walk_static_dir(
+    dir: &Path,
+    cb: &dyn Fn(&DirEntry) -> Result<(), BuildError>,
+) -> Vec<BuildError> {
+    let mut failures = vec![];
+    if !dir.is_dir() {
+        return failures;
+    }
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => return vec![BuildError::io(dir, e)],
+    };
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                failures.push(BuildError::io(dir, e));
+                continue;
             }
+        };
+        let path = entry.path();
+        if path.is_dir() {
+            failures.extend(walk_static_dir(&path, cb));
+        } else if let Err(e) = cb(&entry) {
+            failures.push(e);
         }
     }
-    Ok(())
+    failures
 }
 
-pub fn copy_static_file(dir_entry: &DirEntry) -> io::Result<()> {
-    assert!(!dir_entry.path().is_dir());
-    let proj_path = get_project_dir();
+/// copies static/<path> to public/<path> (unless it's the same as last build)
+pub fn copy_static_file(
+    static_dir: &Path,
+    output_dir: &Path,
+    dir_entry: &DirEntry,
+    cache: &BuildCache,
+) -> Result<(), BuildError> {
     let file_path = dir_entry.path();
-    
-    let file_path_relative_to_static_dir = get_relative_file_path(&file_path, "static");
-    let build_path= proj_path.join("public").join(file_path_relative_to_static_dir);
-    fs::copy(&file_path, &build_path)?;
+    let to_build_error = |e: io::Error| BuildError::io(&file_path, e);
+
+    let file_path_relative_to_static_dir =
+        get_relative_file_path(&file_path, static_dir).map_err(to_build_error)?;
+    let build_path = output_dir.join(file_path_relative_to_static_dir);
+    let fingerprint = file_fingerprint(&file_path).map_err(to_build_error)?;
+    if cache.is_fresh(&build_path, fingerprint) {
+        return Ok(());
+    }
+    if let Some(parent) = build_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| BuildError::io(parent, e))?;
+    }
+    fs::copy(&file_path, &build_path).map_err(to_build_error)?;
+    cache.record(&build_path, fingerprint);
     Ok(())
 }
@@ -1,10 +1,13 @@
 use std::env;
 use std::fs;
 use std::path;
+
+use crate::build::default_templates::DEFAULT_TEMPLATES;
+
 /// Initialize a project in the current dir
 /// # Panics
 /// Panics if a file config file exists in the current dir
-pub fn init_project(project_name: String) {
+pub fn init_project(project_name: String, eject_templates: bool) {
     let cwd = env::current_dir().expect("You have no cwd?");
     let config_file_path = cwd.clone().join("larvae.yaml");
     fs::read(config_file_path).expect_err("You already have a project in this dir");
@@ -13,6 +16,9 @@ pub fn init_project(project_name: String) {
     create_config_file(&cwd, project_name);
     create_gitignore(&cwd);
     create_dirs_and_homepage(&cwd);
+    if eject_templates {
+        create_templates(&cwd);
+    }
 }
 
 fn create_config_file(cwd: &path::PathBuf, project_name: String) {
@@ -23,7 +29,7 @@ fn create_config_file(cwd: &path::PathBuf, project_name: String) {
 
 fn create_gitignore(cwd: &path::PathBuf) {
     let path = cwd.clone().join(".gitignore");
-    let contents = format!("/public");
+    let contents = "/public\n/.megalopa\n";
     fs::write(path, contents).expect("Failure creating gitignore");
 }
 
@@ -42,3 +48,13 @@ fn create_dirs_and_homepage(cwd: &path::PathBuf) {
 
     fs::create_dir(path.join("public")).expect("Failure creating public dir");
 }
+
+/// Writes out the built in templates, files in templates/ override the defaults of the same name
+fn create_templates(cwd: &path::Path) {
+    let templates_dir = cwd.join("templates");
+    fs::create_dir(&templates_dir).expect("Failure creating templates dir");
+    for (file_name, contents) in DEFAULT_TEMPLATES {
+        fs::write(templates_dir.join(file_name), contents)
+            .unwrap_or_else(|_| panic!("Failure creating templates/{file_name}"));
+    }
+}
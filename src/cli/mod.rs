@@ -1,11 +1,16 @@
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 pub mod write_file;
 pub mod init;
+pub mod render;
 
 /// A CLI for growing and curating a crab larva!
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 pub struct Cli {
+    /// The project to use, defaults to the first dir with a larvae.yaml walking up from the cwd
+    #[arg(long, global = true)]
+    pub project: Option<PathBuf>,
     #[command(subcommand)]
     pub command: Command,
 }
@@ -26,11 +31,43 @@ pub enum Command {
     },
     /// Build your md files => html files
     Build {
-         #[arg(default_value_t = false)]
-         empty_out_dir: bool,
+         /// Build into this dir instead of larvae.yaml's dirs.output
+         #[arg(long)]
+         output: Option<PathBuf>,
+         /// Remove everything the last build wrote first (files put in the output dir by hand stay)
+         #[arg(long)]
+         clean: bool,
+         /// Include pages with draft: true
+         #[arg(long)]
+         drafts: bool,
+         /// Include pages with a publish_date that hasn't come yet
+         #[arg(long)]
+         future: bool,
+         /// Render every page, not just the ones that changed since the last build
+         #[arg(long)]
+         force: bool,
     },
     /// Initialize a new project (in cwd)
     Init {
-        project_name: String
+        project_name: String,
+        /// Copy the built in templates into templates/ to customise them
+        #[arg(long)]
+        eject_templates: bool,
+    },
+    /// Render a mustache template to stdout (works outside of a project)
+    Render {
+        template: PathBuf,
+        /// A .json, .yaml or .yml file with the template's data
+        #[arg(long)]
+        data: Option<PathBuf>,
+        /// A dir of *.mustache partials, used by their file name IE: {{> header}}
+        #[arg(long)]
+        partials: Option<PathBuf>,
+        /// Fail on names missing from the data instead of warning about them
+        #[arg(long)]
+        strict: bool,
+        /// Don't html escape anything, for templates that aren't html (IE: config files)
+        #[arg(long)]
+        no_escape: bool,
     },
 }
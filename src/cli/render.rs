@@ -0,0 +1,146 @@
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::html_templating::{json_to_ctx::parse_json_to_ctx, CtxValue, TemplateEngine};
+
+/// Renders a mustache template to stdout, outside of any project
+/// IE: `megalopa render nginx.conf.mustache --data prod.yaml --partials partials --no-escape`
+pub fn render(
+    template_path: &Path,
+    data_path: Option<&Path>,
+    partials_dir: Option<&Path>,
+    strict: bool,
+    escape: bool,
+) -> Result<(), Box<dyn Error>> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    render_template(
+        template_path,
+        data_path,
+        partials_dir,
+        strict,
+        escape,
+        &mut out,
+    )?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Streams the template into out, printing any warnings
+/// on an error (IE: strict mode finding a missing name) some of it may have already been written
+fn render_template(
+    template_path: &Path,
+    data_path: Option<&Path>,
+    partials_dir: Option<&Path>,
+    strict: bool,
+    escape: bool,
+    out: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let context = match data_path {
+        Some(data_path) => read_data(data_path)?,
+        None => CtxValue::HashMap(Box::default()),
+    };
+
+    let mut engine = TemplateEngine::new();
+    engine.set_strict(strict);
+    engine.set_escape(escape);
+    if let Some(partials_dir) = partials_dir {
+        engine
+            .add_templates_dir(partials_dir)
+            .map_err(|e| format!("{}: {e}", partials_dir.display()))?;
+    }
+    let name = template_path
+        .file_stem()
+        .ok_or_else(|| format!("{} isn't a file", template_path.display()))?
+        .to_string_lossy()
+        .to_string();
+    let template_string = fs::read_to_string(template_path)
+        .map_err(|e| format!("{}: {e}", template_path.display()))?;
+    engine.add_template(&name, template_string)?;
+
+    let warnings = engine.render_to(&name, &context, out)?;
+    for warning in warnings {
+        eprintln!("Warning: {warning}");
+    }
+    Ok(())
+}
+
+/// Reads a json or yaml file (picked by it's extension) into a context
+fn read_data(data_path: &Path) -> Result<CtxValue, Box<dyn Error>> {
+    let data_str =
+        fs::read_to_string(data_path).map_err(|e| format!("{}: {e}", data_path.display()))?;
+    let extension = data_path.extension().and_then(|ext| ext.to_str());
+    match extension {
+        Some("json") => {
+            parse_json_to_ctx(&data_str).map_err(|e| format!("{}: {e}", data_path.display()).into())
+        }
+        Some("yaml" | "yml") => serde_yaml::from_str(&data_str)
+            .map_err(|e| format!("{}: {e}", data_path.display()).into()),
+        _ => Err(format!(
+            "{}: data has to be a .json, .yaml or .yml file",
+            data_path.display()
+        )
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn reads_json_and_yaml_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let json_path = dir.path().join("data.json");
+        fs::write(&json_path, "{\"name\": \"megalopa\"}").unwrap();
+        let yaml_path = dir.path().join("data.yaml");
+        fs::write(&yaml_path, "name: megalopa\n").unwrap();
+        let toml_path = dir.path().join("data.toml");
+        fs::write(&toml_path, "name = \"megalopa\"\n").unwrap();
+
+        for path in [json_path, yaml_path] {
+            let CtxValue::HashMap(map) = read_data(&path).unwrap() else {
+                panic!("should be a map")
+            };
+            assert!(matches!(map.get("name"), Some(CtxValue::String(s)) if s == "megalopa"));
+        }
+        assert!(read_data(&toml_path).is_err());
+    }
+
+    #[test]
+    pub fn config_files_can_skip_escaping() {
+        let dir = tempfile::tempdir().unwrap();
+        let template_path = dir.path().join("site.conf.mustache");
+        fs::write(
+            &template_path,
+            "<Directory {{root}}>\n    Require expr \"{{rule}}\"\n</Directory>\n",
+        )
+        .unwrap();
+        let data_path = dir.path().join("data.yaml");
+        fs::write(
+            &data_path,
+            "root: /var/www/a&b\nrule: '%{HTTP_HOST} == \"a.com\"'\n",
+        )
+        .unwrap();
+        let render = |escape| {
+            let mut rendered = vec![];
+            render_template(
+                &template_path,
+                Some(&data_path),
+                None,
+                true,
+                escape,
+                &mut rendered,
+            )
+            .unwrap();
+            String::from_utf8(rendered).unwrap()
+        };
+
+        assert_eq!(
+            render(false),
+            "<Directory /var/www/a&b>\n    Require expr \"%{HTTP_HOST} == \"a.com\"\"\n</Directory>\n"
+        );
+        assert_ne!(render(true), render(false));
+    }
+}
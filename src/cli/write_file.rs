@@ -1,28 +1,49 @@
 use slug;
 use std::fs;
 use std::io::Write;
+use std::path::Path;
 
-use crate::proj_config_utils::get_project_dir;
+use crate::proj_config_utils::read_config;
 
 /// Creates a new md file
-pub fn write_file(title: String, mut content_name: String) -> std::io::Result<()> {
-    let content_template: String = format!("---\ntitle: {}\n----\n\n#", title);
+pub fn write_file(proj_dir: &Path, title: String, mut content_name: String) -> std::io::Result<()> {
+    let content_template: String = format!("---\ntitle: {}\n---\n\n#", title);
     if content_name.len() == 0 {
         content_name = title.clone();
     }
-    let index_template: String = format!("---\ncontent_name: {}\n----\n\n#", content_name);
+    let index_template: String = format!("---\ncontent_name: {}\n---\n\n#", content_name);
 
-    let cwd = get_project_dir();
     let slug = slug::slugify(title);
-    let content_dir = cwd.join("content").join(&content_name);
+    let (config, _) = read_config(proj_dir)?;
+    let content_dir = proj_dir.join(&config.dirs.content).join(&content_name);
     if !content_dir.exists() {
         // if the dir doesn't exist we should make an index.md with the barebones
         fs::create_dir(&content_dir)?;
         fs::write(content_dir.join("index.md"), index_template)?;
     }
-    let mut file_path = cwd.clone().join(content_name).join(slug);
+    let mut file_path = content_dir.join(slug);
     file_path.set_extension("md");
 
     let mut file = fs::File::create_new(&file_path)?;
     file.write_all(content_template.as_bytes())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn writes_into_the_configured_content_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("larvae.yaml"),
+            "title: Site\ndirs:\n  content: pages",
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join("pages")).unwrap();
+
+        write_file(dir.path(), "First Post".to_string(), "posts".to_string()).unwrap();
+        assert!(dir.path().join("pages/posts/index.md").is_file());
+        assert!(dir.path().join("pages/posts/first-post.md").is_file());
+    }
+}
@@ -8,7 +8,118 @@ pub fn escape_html(string: String) -> String {
         '\'' => escaped_string.push_str("&#39;"),
         _ => escaped_string.push(char),
     });
-    return escaped_string;
+    escaped_string
+}
+
+/// Undoes escape_html
+pub fn unescape_html(string: &str) -> String {
+    string
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// For attribute values without quotes, where whitespace or an = would end the value
+pub fn escape_unquoted_attribute(string: &str) -> String {
+    let mut escaped_string = String::new();
+    string.chars().for_each(|char| match char {
+        '<' => escaped_string.push_str("&lt;"),
+        '>' => escaped_string.push_str("&gt;"),
+        '&' => escaped_string.push_str("&amp;"),
+        '"' => escaped_string.push_str("&quot;"),
+        '\'' => escaped_string.push_str("&#39;"),
+        '=' => escaped_string.push_str("&#61;"),
+        '`' => escaped_string.push_str("&#96;"),
+        c if c.is_whitespace() => escaped_string.push_str(&format!("&#{};", c as u32)),
+        _ => escaped_string.push(char),
+    });
+    escaped_string
+}
+
+/// Percent encodes what can't be in a url (or in a query value, which leaves only unreserved chars)
+/// A url starting with a scheme other than http(s), mailto or tel (IE: javascript:) is swapped out
+pub fn normalize_url(url: &str, is_start: bool, is_query: bool) -> String {
+    if is_start && !is_query && has_unsafe_scheme(url) {
+        return "#unsafe-url".to_string();
+    }
+    let mut normalized = String::new();
+    for byte in url.bytes() {
+        let is_allowed = byte.is_ascii_alphanumeric()
+            || b"-._~".contains(&byte)
+            || (!is_query && b":/?#[]@!$&'()*+,;=%".contains(&byte));
+        if is_allowed {
+            normalized.push(byte as char);
+        } else {
+            normalized.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    normalized
+}
+
+/// srcset is a list of urls, each with an optional descriptor after a space IE: "a.png 1x, b.png 2x"
+/// each url is normalized on it's own (is_start is for the first), the spaces and commas between them are kept
+pub fn normalize_srcset(srcset: &str, is_start: bool) -> String {
+    let mut candidates = vec![];
+    let mut rest = srcset;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+        let url_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let (url, after_url) = rest.split_at(url_end);
+        // a url ending with a comma has no descriptor
+        let (url, descriptor, after_candidate) = match url.strip_suffix(',') {
+            Some(url) => (url.trim_end_matches(','), "", after_url),
+            None => {
+                let descriptor_end = after_url.find(',').unwrap_or(after_url.len());
+                let (descriptor, after_descriptor) = after_url.split_at(descriptor_end);
+                (url, descriptor.trim(), after_descriptor)
+            }
+        };
+        let url = normalize_url(url, is_start || !candidates.is_empty(), false);
+        candidates.push(if descriptor.is_empty() {
+            url
+        } else {
+            format!("{url} {descriptor}")
+        });
+        rest = after_candidate;
+    }
+    candidates.join(", ")
+}
+
+fn has_unsafe_scheme(url: &str) -> bool {
+    let url = url.trim_start();
+    let Some(colon_idx) = url.find(':') else {
+        return false;
+    };
+    // a colon after a path, query or fragment started isn't a scheme IE: "/posts?time=10:30"
+    if url[..colon_idx].contains(['/', '?', '#']) {
+        return false;
+    }
+    let scheme = url[..colon_idx].to_ascii_lowercase();
+    !matches!(scheme.as_str(), "http" | "https" | "mailto" | "tel")
+}
+
+/// For values in a script, safe inside a js string and can't close the <script> tag
+/// only uses \ escapes so it's also safe inside an event handler attribute
+pub fn escape_js_string(string: &str) -> String {
+    let mut escaped_string = String::new();
+    string.chars().for_each(|char| match char {
+        '\\' => escaped_string.push_str("\\\\"),
+        '/' => escaped_string.push_str("\\/"),
+        '\n' => escaped_string.push_str("\\n"),
+        '\r' => escaped_string.push_str("\\r"),
+        '\t' => escaped_string.push_str("\\t"),
+        '"' | '\'' | '`' | '<' | '>' | '&' | '=' | '\u{2028}' | '\u{2029}' => {
+            escaped_string.push_str(&format!("\\u{:04X}", char as u32))
+        }
+        c if c.is_control() => escaped_string.push_str(&format!("\\u{:04X}", c as u32)),
+        _ => escaped_string.push(char),
+    });
+    escaped_string
 }
 
 #[cfg(test)]
@@ -19,5 +130,12 @@ mod tests {
     let escaped_html = escape_html("hello & world <script>alert(\"Uh oh\")</script>".to_string());
     assert_eq!(escaped_html, "hello &amp; world &lt;script&gt;alert(&quot;Uh oh&quot;)&lt;/script&gt;".to_string())
   }
+  #[test]
+  pub fn srcset_urls_are_normalized_one_by_one() {
+    assert_eq!(normalize_srcset("a.png 1x,b.png  2x", true), "a.png 1x, b.png 2x");
+    assert_eq!(normalize_srcset("/img/a%20b.png 480w,/img/big.png,", true), "/img/a%20b.png 480w, /img/big.png");
+    assert_eq!(normalize_srcset("a.png, javascript:alert(1) 2x", true), "a.png, #unsafe-url 2x");
+    assert_eq!(normalize_srcset("/a,b.png 1x", false), "/a,b.png 1x");
+  }
 
 }
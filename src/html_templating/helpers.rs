@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+use super::escape_html::unescape_html;
+use super::CtxValue;
+use crate::markdown_parsing::parse_markdown;
+
+/// A named helper used as a section: `{{#truncate 20}}{{ description }}{{/truncate}}`
+/// Called with the rendered section content and whatever follows the name in the opening tag
+/// The returned string is output as is (no html escaping)
+pub type Helper = Arc<dyn Fn(&str, &str) -> String + Send + Sync>;
+
+/// A named helper used as a section that looks up data to render the section with:
+/// `{{#get_page posts/first}}{{ title }}{{/get_page}}` (a list renders the section for each item)
+/// Called with whatever follows the name in the opening tag, None when there's nothing to find
+pub type DataHelper = Arc<dyn Fn(&str) -> Option<CtxValue> + Send + Sync>;
+
+pub fn default_helpers() -> HashMap<String, Helper> {
+    let mut helpers: HashMap<String, Helper> = HashMap::new();
+    helpers.insert("date".to_string(), Arc::new(format_date));
+    helpers.insert("slugify".to_string(), Arc::new(slugify));
+    helpers.insert("truncate".to_string(), Arc::new(truncate));
+    helpers.insert("markdown".to_string(), Arc::new(markdown));
+    helpers
+}
+
+/// `{{#date %d/%m/%Y}}{{ date }}{{/date}}` (defaults to "May 1, 2024")
+/// content that isn't a date is passed through untouched
+fn format_date(content: &str, format: &str) -> String {
+    let format = if format.is_empty() {
+        "%B %-d, %Y"
+    } else {
+        format
+    };
+    match parse_date(content.trim()) {
+        Some(date) => date.format(format).to_string(),
+        None => content.to_string(),
+    }
+}
+
+/// Accepts "2024-05-01", "2024-05-01 10:30:00", "2024-05-01T10:30:00" or an RFC 3339 timestamp
+pub fn parse_date(date_str: &str) -> Option<NaiveDateTime> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(date_str) {
+        return Some(date.naive_utc());
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(date_str, format) {
+            return Some(date);
+        }
+    }
+    NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+}
+
+fn slugify(content: &str, _: &str) -> String {
+    slug::slugify(content)
+}
+
+/// `{{#truncate 50}}...{{/truncate}}` cuts the content down to a number of chars (default 150)
+fn truncate(content: &str, max_chars: &str) -> String {
+    let max_chars = max_chars.trim().parse::<usize>().unwrap_or(150);
+    if content.chars().count() <= max_chars {
+        return content.to_string();
+    }
+    let mut truncated: String = content.chars().take(max_chars).collect();
+    truncated = truncated.trim_end().to_string();
+    truncated.push('…');
+    truncated
+}
+
+/// The content's html escaped like any section's, which is left as is except in code spans
+/// markdown escapes those itself, so they're unescaped first (IE: `&lt;tag&gt;` isn't shown as &amp;lt;tag&amp;gt;)
+fn markdown(content: &str, _: &str) -> String {
+    let mut md = String::new();
+    let mut parts = content.split('`').peekable();
+    let mut is_code = false;
+    while let Some(part) = parts.next() {
+        // a ` without a closing one doesn't start a code span
+        if is_code && parts.peek().is_some() {
+            md.push_str(&unescape_html(part));
+        } else {
+            md.push_str(part);
+        }
+        if parts.peek().is_some() {
+            md.push('`');
+        }
+        is_code = !is_code;
+    }
+    parse_markdown(&md)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn date_formats() {
+        assert_eq!(format_date("2024-05-01", ""), "May 1, 2024");
+        assert_eq!(
+            format_date("2024-05-01T10:30:00Z", "%d/%m/%Y %H:%M"),
+            "01/05/2024 10:30"
+        );
+        assert_eq!(format_date("not a date", ""), "not a date");
+    }
+
+    #[test]
+    pub fn truncating() {
+        assert_eq!(truncate("hello world", "5"), "hello…");
+        assert_eq!(truncate("hello", "5"), "hello");
+    }
+}
@@ -0,0 +1,596 @@
+use super::escape_html::{
+    escape_html, escape_js_string, escape_unquoted_attribute, normalize_srcset, normalize_url,
+};
+use super::render_template::interpolate;
+use super::{CtxValue, Number};
+
+/// Where an interpolation tag sits in the surrounding html, which decides how it's escaped
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HtmlContext {
+    /// An element's body (or anywhere we don't know any better)
+    Text,
+    Attribute {
+        is_quoted: bool,
+    },
+    /// The value of an attribute holding a url like href or src
+    Url {
+        is_quoted: bool,
+        /// nothing comes before the tag so it decides the url's scheme
+        is_start: bool,
+        /// after the ? so it's percent encoded as a query value
+        is_query: bool,
+    },
+    /// The value of a srcset attribute, a list of urls with their descriptors
+    SrcSet {
+        is_quoted: bool,
+        /// nothing (or only other urls) comes before the tag so it starts a url
+        is_start: bool,
+    },
+    /// Inside a <script> or an on* event handler
+    /// in a js string literal values are escaped to be safe in it, anywhere else they're written as js literals
+    Script {
+        is_string: bool,
+        /// an on* attribute's value, so it's html escaped too
+        is_attribute: bool,
+    },
+}
+
+/// Attributes whose value is a url
+const URL_ATTRIBUTES: [&str; 8] = [
+    "href",
+    "src",
+    "action",
+    "formaction",
+    "cite",
+    "poster",
+    "background",
+    "longdesc",
+];
+
+impl HtmlContext {
+    /// Escapes an interpolated value so it can't break out of this context
+    /// IE: a title of `" onclick="alert(1)` can't leave `<a title="{{ title }}">`
+    pub fn escape(&self, value: String) -> String {
+        match self {
+            HtmlContext::Text => escape_html(value),
+            HtmlContext::Attribute { is_quoted: true } => escape_html(value),
+            HtmlContext::Attribute { is_quoted: false } => escape_unquoted_attribute(&value),
+            HtmlContext::Url {
+                is_quoted,
+                is_start,
+                is_query,
+            } => {
+                let url = normalize_url(&value, *is_start, *is_query);
+                if *is_quoted {
+                    escape_html(url)
+                } else {
+                    escape_unquoted_attribute(&url)
+                }
+            }
+            HtmlContext::SrcSet {
+                is_quoted,
+                is_start,
+            } => {
+                let srcset = normalize_srcset(&value, *is_start);
+                if *is_quoted {
+                    escape_html(srcset)
+                } else {
+                    escape_unquoted_attribute(&srcset)
+                }
+            }
+            HtmlContext::Script {
+                is_string: true, ..
+            } => escape_js_string(&value),
+            HtmlContext::Script { .. } => self.escape_value(&CtxValue::String(value)),
+        }
+    }
+
+    /// Escapes a value from the context, which outside a js string keeps it's type
+    /// IE: `let n = {{ count }};` writes a number, and a string comes with it's quotes
+    pub fn escape_value(&self, value: &CtxValue) -> String {
+        match self {
+            HtmlContext::Script {
+                is_string: false,
+                is_attribute,
+            } => {
+                let literal = js_literal(value);
+                if *is_attribute {
+                    escape_html(literal)
+                } else {
+                    literal
+                }
+            }
+            _ => self.escape(interpolate(value)),
+        }
+    }
+
+    fn of_attribute(name: &str, is_quoted: bool, value: &str) -> HtmlContext {
+        if name.starts_with("on") {
+            HtmlContext::Script {
+                is_string: value.chars().fold(Js::Code, Js::after_char).is_string(),
+                is_attribute: true,
+            }
+        } else if name == "srcset" {
+            let value = value.trim_end();
+            HtmlContext::SrcSet {
+                is_quoted,
+                is_start: value.is_empty() || value.ends_with(','),
+            }
+        } else if URL_ATTRIBUTES.contains(&name) {
+            HtmlContext::Url {
+                is_quoted,
+                is_start: value.trim().is_empty(),
+                is_query: value.contains('?'),
+            }
+        } else {
+            HtmlContext::Attribute { is_quoted }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum State {
+    Text,
+    /// right after a '<'
+    TagName {
+        name: String,
+        is_closing: bool,
+    },
+    InTag {
+        opens_script: bool,
+    },
+    AttrName {
+        name: String,
+        opens_script: bool,
+    },
+    AfterAttrName {
+        name: String,
+        opens_script: bool,
+    },
+    BeforeAttrValue {
+        name: String,
+        opens_script: bool,
+    },
+    AttrValue {
+        name: String,
+        quote: Option<char>,
+        value: String,
+        opens_script: bool,
+    },
+    /// the tail is just enough of the script to spot it's closing tag (which closes it even inside a js string)
+    Script {
+        tail: String,
+        js: Js,
+    },
+    Comment {
+        tail: String,
+    },
+}
+
+/// Follows the html in a template's text (skipping over the tags) to know the context of each tag
+/// it's a rough html tokenizer, just enough to tell element bodies, attributes and scripts apart
+pub struct HtmlContextTracker {
+    state: State,
+}
+
+impl HtmlContextTracker {
+    pub fn new() -> HtmlContextTracker {
+        HtmlContextTracker { state: State::Text }
+    }
+
+    pub fn context(&self) -> HtmlContext {
+        match &self.state {
+            State::Text | State::Comment { .. } => HtmlContext::Text,
+            State::Script { js, .. } => HtmlContext::Script {
+                is_string: js.is_string(),
+                is_attribute: false,
+            },
+            State::TagName { .. }
+            | State::InTag { .. }
+            | State::AttrName { .. }
+            | State::AfterAttrName { .. } => HtmlContext::Attribute { is_quoted: false },
+            State::BeforeAttrValue { name, .. } => HtmlContext::of_attribute(name, false, ""),
+            State::AttrValue {
+                name, quote, value, ..
+            } => HtmlContext::of_attribute(name, quote.is_some(), value),
+        }
+    }
+
+    /// An interpolation was output here, so an attribute value has started
+    /// IE: the second tag in `href="{{ base }}{{ path }}"` doesn't start the url
+    pub fn interpolated(&mut self) {
+        match &mut self.state {
+            State::BeforeAttrValue { name, opens_script } => {
+                self.state = State::AttrValue {
+                    name: std::mem::take(name),
+                    quote: None,
+                    value: "_".to_string(),
+                    opens_script: *opens_script,
+                }
+            }
+            State::AttrValue { value, .. } => value.push('_'),
+            _ => {}
+        }
+    }
+
+    pub fn feed(&mut self, text: &str) {
+        for c in text.chars() {
+            self.feed_char(c);
+        }
+    }
+
+    fn feed_char(&mut self, c: char) {
+        let state = std::mem::replace(&mut self.state, State::Text);
+        self.state = match state {
+            State::Text if c == '<' => State::TagName {
+                name: String::new(),
+                is_closing: false,
+            },
+            State::Text => State::Text,
+            State::TagName { name, is_closing } => match c {
+                '/' if name.is_empty() && !is_closing => State::TagName {
+                    name,
+                    is_closing: true,
+                },
+                '!' | '-'
+                    if !is_closing && (name.starts_with('!') || (c == '!' && name.is_empty())) =>
+                {
+                    let name = format!("{name}{c}");
+                    if name == "!--" {
+                        State::Comment {
+                            tail: String::new(),
+                        }
+                    } else {
+                        State::TagName { name, is_closing }
+                    }
+                }
+                c if c.is_ascii_alphanumeric() => State::TagName {
+                    name: format!("{name}{}", c.to_ascii_lowercase()),
+                    is_closing,
+                },
+                // "a < b" isn't a tag
+                _ if name.is_empty() => State::Text,
+                c => {
+                    let opens_script = !is_closing && name == "script";
+                    State::InTag { opens_script }.after_tag_char(c)
+                }
+            },
+            State::InTag { .. } => state.after_tag_char(c),
+            State::AttrName { name, opens_script } => match c {
+                '=' => State::BeforeAttrValue { name, opens_script },
+                c if c.is_whitespace() => State::AfterAttrName { name, opens_script },
+                '>' | '/' => State::InTag { opens_script }.after_tag_char(c),
+                c => State::AttrName {
+                    name: format!("{name}{}", c.to_ascii_lowercase()),
+                    opens_script,
+                },
+            },
+            State::AfterAttrName { name, opens_script } => match c {
+                '=' => State::BeforeAttrValue { name, opens_script },
+                c if c.is_whitespace() => State::AfterAttrName { name, opens_script },
+                c => State::InTag { opens_script }.after_tag_char(c),
+            },
+            State::BeforeAttrValue { name, opens_script } => match c {
+                '"' | '\'' => State::AttrValue {
+                    name,
+                    quote: Some(c),
+                    value: String::new(),
+                    opens_script,
+                },
+                c if c.is_whitespace() => State::BeforeAttrValue { name, opens_script },
+                '>' => State::InTag { opens_script }.after_tag_char(c),
+                c => State::AttrValue {
+                    name,
+                    quote: None,
+                    value: c.to_string(),
+                    opens_script,
+                },
+            },
+            State::AttrValue {
+                name,
+                quote,
+                mut value,
+                opens_script,
+            } => match quote {
+                Some(quote) if c == quote => State::InTag { opens_script },
+                None if c.is_whitespace() => State::InTag { opens_script },
+                None if c == '>' => State::InTag { opens_script }.after_tag_char(c),
+                _ => {
+                    value.push(c);
+                    State::AttrValue {
+                        name,
+                        quote,
+                        value,
+                        opens_script,
+                    }
+                }
+            },
+            State::Script { tail, js } => {
+                let tail = push_to_tail(tail, c, "</script".len());
+                if tail.eq_ignore_ascii_case("</script") {
+                    State::TagName {
+                        name: "script".to_string(),
+                        is_closing: true,
+                    }
+                } else {
+                    State::Script {
+                        tail,
+                        js: js.after_char(c),
+                    }
+                }
+            }
+            State::Comment { tail } => {
+                let tail = push_to_tail(tail, c, "-->".len());
+                if tail == "-->" {
+                    State::Text
+                } else {
+                    State::Comment { tail }
+                }
+            }
+        };
+    }
+}
+
+impl Default for HtmlContextTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl State {
+    /// Handles a char between attributes (self must be InTag)
+    fn after_tag_char(self, c: char) -> State {
+        let State::InTag { opens_script } = self else {
+            return self;
+        };
+        match c {
+            '>' if opens_script => State::Script {
+                tail: String::new(),
+                js: Js::Code,
+            },
+            '>' => State::Text,
+            c if c.is_whitespace() || c == '/' => self,
+            c => State::AttrName {
+                name: c.to_ascii_lowercase().to_string(),
+                opens_script,
+            },
+        }
+    }
+}
+
+/// Where we are in a script, just enough js to know when we're in a string literal
+/// (regex literals and the code in a template literal's ${} aren't followed)
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Js {
+    Code,
+    /// a / that might start a comment
+    Slash,
+    String {
+        quote: char,
+        is_escaped: bool,
+    },
+    LineComment,
+    BlockComment {
+        /// after a *
+        is_closing: bool,
+    },
+}
+
+impl Js {
+    fn after_char(self, c: char) -> Js {
+        match (self, c) {
+            (Js::Code, '"' | '\'' | '`') => Js::String {
+                quote: c,
+                is_escaped: false,
+            },
+            (Js::Code, '/') => Js::Slash,
+            (Js::Code, _) => Js::Code,
+            (Js::Slash, '/') => Js::LineComment,
+            (Js::Slash, '*') => Js::BlockComment { is_closing: false },
+            (Js::Slash, c) => Js::Code.after_char(c),
+            (
+                Js::String {
+                    quote,
+                    is_escaped: false,
+                },
+                '\\',
+            ) => Js::String {
+                quote,
+                is_escaped: true,
+            },
+            (
+                Js::String {
+                    quote,
+                    is_escaped: false,
+                },
+                c,
+            ) if c == quote => Js::Code,
+            (Js::String { quote, .. }, _) => Js::String {
+                quote,
+                is_escaped: false,
+            },
+            (Js::LineComment, '\n') => Js::Code,
+            (Js::LineComment, _) => Js::LineComment,
+            (Js::BlockComment { is_closing: true }, '/') => Js::Code,
+            (Js::BlockComment { .. }, c) => Js::BlockComment {
+                is_closing: c == '*',
+            },
+        }
+    }
+
+    fn is_string(self) -> bool {
+        matches!(self, Js::String { .. })
+    }
+}
+
+/// A value written into a script outside of a string, as json (which is valid js)
+/// strings get the same escapes as inside a js string so they can't close the <script> tag
+fn js_literal(value: &CtxValue) -> String {
+    match value {
+        CtxValue::String(s) => format!("\"{}\"", escape_js_string(s)),
+        CtxValue::Number(Number::Float(float)) if !float.is_finite() => "null".to_string(),
+        CtxValue::Number(n) => n.to_string(),
+        CtxValue::Boolean(b) => b.to_string(),
+        CtxValue::Null => "null".to_string(),
+        CtxValue::List(items) => {
+            let items: Vec<String> = items.iter().map(js_literal).collect();
+            format!("[{}]", items.join(","))
+        }
+        CtxValue::HashMap(map) => {
+            // sorted so the output doesn't change from build to build
+            let mut entries: Vec<(&String, &CtxValue)> = map.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            let entries: Vec<String> = entries
+                .into_iter()
+                .map(|(key, value)| format!("\"{}\":{}", escape_js_string(key), js_literal(value)))
+                .collect();
+            format!("{{{}}}", entries.join(","))
+        }
+        // rendered by the lambda into a string before it gets here
+        CtxValue::Lambda(_) => "null".to_string(),
+    }
+}
+
+/// Keeps the last len chars
+fn push_to_tail(mut tail: String, c: char, len: usize) -> String {
+    tail.push(c);
+    if tail.chars().count() > len {
+        tail.remove(0);
+    }
+    tail
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context_after(html: &str) -> HtmlContext {
+        let mut tracker = HtmlContextTracker::new();
+        tracker.feed(html);
+        tracker.context()
+    }
+
+    #[test]
+    pub fn tracks_contexts() {
+        assert_eq!(context_after("<p>a < b "), HtmlContext::Text);
+        assert_eq!(
+            context_after("<img srcset=\"a.png 1x, "),
+            HtmlContext::SrcSet {
+                is_quoted: true,
+                is_start: true
+            }
+        );
+        assert_eq!(
+            context_after("<a class=\"x\" title='"),
+            HtmlContext::Attribute { is_quoted: true }
+        );
+        assert_eq!(
+            context_after("<a href=\"/posts?page="),
+            HtmlContext::Url {
+                is_quoted: true,
+                is_start: false,
+                is_query: true
+            }
+        );
+        assert_eq!(
+            context_after("<img src="),
+            HtmlContext::Url {
+                is_quoted: false,
+                is_start: true,
+                is_query: false
+            }
+        );
+        assert_eq!(
+            context_after("<button onclick=\"go('"),
+            HtmlContext::Script {
+                is_string: true,
+                is_attribute: true
+            }
+        );
+        assert_eq!(
+            context_after("<button onclick=\"go('a', "),
+            HtmlContext::Script {
+                is_string: false,
+                is_attribute: true
+            }
+        );
+        assert_eq!(
+            context_after("<script type=\"module\">let title = \""),
+            HtmlContext::Script {
+                is_string: true,
+                is_attribute: false
+            }
+        );
+        assert_eq!(
+            context_after("<script>let t = \"a \\\" b\"; // don't\n/* it's */ let n = "),
+            HtmlContext::Script {
+                is_string: false,
+                is_attribute: false
+            }
+        );
+        assert_eq!(
+            context_after("<script>let a = 1;</SCRIPT>\n<p>"),
+            HtmlContext::Text
+        );
+        assert_eq!(context_after("<!-- <a href=\" -->"), HtmlContext::Text);
+    }
+
+    #[test]
+    pub fn escapes_for_context() {
+        let title = "\" onclick=\"alert(1)".to_string();
+        assert_eq!(
+            HtmlContext::Attribute { is_quoted: true }.escape(title.clone()),
+            "&quot; onclick=&quot;alert(1)"
+        );
+        assert_eq!(
+            HtmlContext::Attribute { is_quoted: false }.escape(title),
+            "&quot;&#32;onclick&#61;&quot;alert(1)"
+        );
+        let url = HtmlContext::Url {
+            is_quoted: true,
+            is_start: true,
+            is_query: false,
+        };
+        assert_eq!(
+            url.escape("/posts/hi there".to_string()),
+            "/posts/hi%20there"
+        );
+        assert_eq!(url.escape("javascript:alert(1)".to_string()), "#unsafe-url");
+        let query = HtmlContext::Url {
+            is_quoted: true,
+            is_start: false,
+            is_query: true,
+        };
+        assert_eq!(query.escape("a&b=c".to_string()), "a%26b%3Dc");
+        let string = HtmlContext::Script {
+            is_string: true,
+            is_attribute: false,
+        };
+        assert_eq!(
+            string.escape("</script>\"'".to_string()),
+            "\\u003C\\/script\\u003E\\u0022\\u0027"
+        );
+        let script = HtmlContext::Script {
+            is_string: false,
+            is_attribute: false,
+        };
+        assert_eq!(
+            script.escape("</script>".to_string()),
+            "\"\\u003C\\/script\\u003E\""
+        );
+        let list = CtxValue::List(Box::new(vec![
+            CtxValue::Number(Number::Integer(3)),
+            CtxValue::Boolean(true),
+            CtxValue::Null,
+        ]));
+        assert_eq!(script.escape_value(&list), "[3,true,null]");
+        let handler = HtmlContext::Script {
+            is_string: false,
+            is_attribute: true,
+        };
+        assert_eq!(
+            handler.escape_value(&CtxValue::String("a".to_string())),
+            "&quot;a&quot;"
+        );
+    }
+}
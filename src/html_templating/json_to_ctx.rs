@@ -1,9 +1,38 @@
-use super::CtxValue;
+use std::{collections::HashMap, error::Error, fmt, iter::Peekable, str::Chars};
 
-//
-pub fn parse_json_to_ctx(json_str: String) -> CtxValue {
-    let mut char_iter = json_str.chars();
-    
+use super::{CtxValue, Number};
+
+#[derive(Debug, PartialEq)]
+pub struct JsonParseError {
+    pub message: String,
+    /// 1 indexed position of where the parser gave up
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for JsonParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {} column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl Error for JsonParseError {}
+
+/// How deep lists and objects can be nested, past it's an error rather than overflowing the stack
+const MAX_DEPTH: usize = 128;
+
+/// Parses a json document into a CtxValue
+pub fn parse_json_to_ctx(json_str: &str) -> Result<CtxValue, JsonParseError> {
+    let mut parser = JsonParser {
+        chars: json_str.chars().peekable(),
+        line: 1,
+        column: 1,
+        depth: 0,
+    };
     // we need to determine if it's a valid json value
     // Either:
     // - String
@@ -12,102 +41,345 @@ pub fn parse_json_to_ctx(json_str: String) -> CtxValue {
     // - number
     // - "true"/"false"/"null"
     // List or Object we dig into the object and recurse on sub values
-    // everything else we just return
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if let Some(c) = parser.peek() {
+        return Err(parser.error(format!("Unexpected '{c}' after the json value")));
+    }
+    Ok(value)
+}
+
+struct JsonParser<'a> {
+    chars: Peekable<Chars<'a>>,
+    /// position of the next char
+    line: usize,
+    column: usize,
+    /// lists and objects the parser is in
+    depth: usize,
+}
+
+impl JsonParser<'_> {
+    fn error(&self, message: String) -> JsonParseError {
+        JsonParseError {
+            message,
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
 
-    while let Some(c) = char_iter.next() {
-        match c {
-            '[' => {
-                // open a list
-                let mut list = CtxValue::List(Box::new(vec![]));
+    fn skip_whitespace(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|c| matches!(c, ' ' | '\n' | '\r' | '\t'))
+        {
+            self.next_char();
+        }
+    }
 
-                let mut rest_of_json_str: String = char_iter.clone().collect();
-                rest_of_json_str.insert(0, c);
-                let list_str = slice_until_closing_bracket(rest_of_json_str);
-                let char_len = list_str.chars().count();
-                // char_len - 1 for the first bracket the iter is already passed -1 for 0 indexing
-                char_iter.nth(char_len - 2);
+    /// Consumes expected or errors out
+    fn expect_char(&mut self, expected: char) -> Result<(), JsonParseError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.next_char();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("Expected '{expected}' but found '{c}'"))),
+            None => Err(self.error(format!("Expected '{expected}' but the json ended"))),
+        }
+    }
 
-                // split on , (not inside strings) how can I split on true commas
-                // I suppose valid json will only split on ], or }, but what about keys!!!!! 
-                // each split can be a nested obj / other list
-                
-                // so we're trying to split up the contents is this it's own fn??
+    fn parse_value(&mut self) -> Result<CtxValue, JsonParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.parse_nested(Self::parse_object),
+            Some('[') => self.parse_nested(Self::parse_list),
+            Some('"') => Ok(CtxValue::String(self.parse_string()?)),
+            Some('t') => self.parse_literal("true", CtxValue::Boolean(true)),
+            Some('f') => self.parse_literal("false", CtxValue::Boolean(false)),
+            Some('n') => self.parse_literal("null", CtxValue::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number(),
+            Some(c) => Err(self.error(format!("Unexpected '{c}'"))),
+            None => Err(self.error("Expected a value but the json ended".to_string())),
+        }
+    }
 
+    /// Lists and objects recurse on their values
+    fn parse_nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<CtxValue, JsonParseError>,
+    ) -> Result<CtxValue, JsonParseError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(format!("Nested more than {MAX_DEPTH} levels deep")));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
 
+    fn parse_literal(
+        &mut self,
+        literal: &str,
+        value: CtxValue,
+    ) -> Result<CtxValue, JsonParseError> {
+        for expected in literal.chars() {
+            if self.peek() != Some(expected) {
+                return Err(self.error(format!("Invalid literal, expected \"{literal}\"")));
             }
-            _ => {}
+            self.next_char();
+        }
+        Ok(value)
+    }
+
+    fn parse_number(&mut self) -> Result<CtxValue, JsonParseError> {
+        let (line, column) = (self.line, self.column);
+        let mut number_str = String::new();
+        while let Some(c) = self
+            .peek()
+            .filter(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            number_str.push(c);
+            self.next_char();
+        }
+        let invalid_number = || JsonParseError {
+            message: format!("Invalid number \"{number_str}\""),
+            line,
+            column,
         };
+        // json doesn't allow leading zeros or a bare "-"
+        let digits = number_str.strip_prefix('-').unwrap_or(&number_str);
+        if !digits.starts_with(|c: char| c.is_ascii_digit())
+            || (digits.starts_with('0') && digits[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            return Err(invalid_number());
+        }
+        if number_str.contains(['.', 'e', 'E']) {
+            let float = number_str.parse::<f64>().map_err(|_| invalid_number())?;
+            return Ok(CtxValue::Number(Number::Float(float)));
+        }
+        match number_str.parse::<i64>() {
+            Ok(integer) => Ok(CtxValue::Number(Number::Integer(integer))),
+            // too big for an i64 but still a valid number
+            Err(_) => {
+                let float = number_str.parse::<f64>().map_err(|_| invalid_number())?;
+                Ok(CtxValue::Number(Number::Float(float)))
+            }
+        }
     }
-    todo!()
-}
 
-/// valid starting chars are '[' or '{'
-/// Returns the json string opened by the starting char till it's closing char
-/// IE: "{ a: 1 }, b: { c: 1 }}]" => "{ a: 1 }"
-pub fn slice_until_closing_bracket(json_str: String) -> String {
-    let mut json = String::new();
-    let mut char_iter = json_str.chars();
-    let opening_char = char_iter.clone().next().expect("Should be an opening char");
-    assert!(opening_char == '[' || opening_char == '{');
-    // first char will add one
-    let mut braces_currently_open = 0;
-    while let Some(c) = char_iter.next() {
-        match c {
-            '[' | '{' => {
-                braces_currently_open += 1;
+    fn parse_string(&mut self) -> Result<String, JsonParseError> {
+        self.expect_char('"')?;
+        let mut string = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("Unterminated string".to_string())),
+                Some('"') => {
+                    self.next_char();
+                    return Ok(string);
+                }
+                Some('\\') => {
+                    self.next_char();
+                    let escaped = match self.peek() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            self.next_char();
+                            string.push(self.parse_unicode_escape()?);
+                            continue;
+                        }
+                        Some(c) => return Err(self.error(format!("Invalid escape '\\{c}'"))),
+                        None => return Err(self.error("Unterminated string".to_string())),
+                    };
+                    self.next_char();
+                    string.push(escaped);
+                }
+                Some(c) if c.is_control() => {
+                    return Err(self.error("Unescaped control character in string".to_string()))
+                }
+                Some(_) => string.push(self.next_char().expect("just peeked")),
             }
-            ']' | '}' => {
-                braces_currently_open -= 1;
+        }
+    }
+
+    /// The part after a \u, characters outside the BMP come as two escaped surrogates
+    fn parse_unicode_escape(&mut self) -> Result<char, JsonParseError> {
+        let code_unit = self.parse_hex_code_unit()?;
+        if (0xD800..0xDC00).contains(&code_unit) {
+            self.expect_char('\\')?;
+            self.expect_char('u')?;
+            let low_surrogate = self.parse_hex_code_unit()?;
+            if !(0xDC00..0xE000).contains(&low_surrogate) {
+                return Err(self.error("Invalid unicode surrogate pair".to_string()));
             }
-            '\"' => {
-                json.push(c);
-                // we need to skip the char iter to the closing "
-                // so we can avoid any brackets in the key/value
-                while let Some(char) = char_iter.next() {
-                    if char == '\"' {
-                        break;
-                    }
-                    json.push(char);
+            let code_point = 0x10000 + ((code_unit - 0xD800) << 10) + (low_surrogate - 0xDC00);
+            return char::from_u32(code_point)
+                .ok_or_else(|| self.error("Invalid unicode escape".to_string()));
+        }
+        char::from_u32(code_unit).ok_or_else(|| self.error("Invalid unicode escape".to_string()))
+    }
+
+    fn parse_hex_code_unit(&mut self) -> Result<u32, JsonParseError> {
+        let mut code_unit = 0;
+        for _ in 0..4 {
+            let digit = self
+                .chars
+                .peek()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("Expected 4 hex digits after \\u".to_string()))?;
+            self.next_char();
+            code_unit = code_unit * 16 + digit;
+        }
+        Ok(code_unit)
+    }
+
+    fn parse_list(&mut self) -> Result<CtxValue, JsonParseError> {
+        self.expect_char('[')?;
+        let mut list = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.next_char();
+            return Ok(CtxValue::List(Box::new(list)));
+        }
+        loop {
+            list.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.next_char();
+                }
+                Some(']') => {
+                    self.next_char();
+                    return Ok(CtxValue::List(Box::new(list)));
                 }
+                Some(c) => return Err(self.error(format!("Expected ',' or ']' but found '{c}'"))),
+                None => return Err(self.error("Unterminated list".to_string())),
             }
-            _ => {}
         }
-        json.push(c);
-        if braces_currently_open == 0 {
-            break;
+    }
+
+    fn parse_object(&mut self) -> Result<CtxValue, JsonParseError> {
+        self.expect_char('{')?;
+        let mut map = HashMap::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.next_char();
+            return Ok(CtxValue::HashMap(Box::new(map)));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect_char(':')?;
+            let value = self.parse_value()?;
+            map.insert(key, value);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.next_char();
+                }
+                Some('}') => {
+                    self.next_char();
+                    return Ok(CtxValue::HashMap(Box::new(map)));
+                }
+                Some(c) => return Err(self.error(format!("Expected ',' or '}}' but found '{c}'"))),
+                None => return Err(self.error("Unterminated object".to_string())),
+            }
         }
     }
-    return json;
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::html_templating::{json_to_ctx, CtxValue};
+    use super::*;
 
-    use super::slice_until_closing_bracket;
+    fn get<'a>(value: &'a CtxValue, key: &str) -> &'a CtxValue {
+        match value {
+            CtxValue::HashMap(map) => map.get(key).expect("key should be there"),
+            _ => panic!("not an object"),
+        }
+    }
 
     #[test]
     pub fn nested_lists() {
-        let json_str = "[[]],[]]".to_string();
-        let res = slice_until_closing_bracket(json_str);
-        assert_eq!(res, "[[]]".to_string());
-    }
-    #[test]
-    pub fn brackets() {
-        let json_str = "{[], {}}, {}]".to_string();
-        let res = slice_until_closing_bracket(json_str);
-        assert_eq!(res, "{[], {}}".to_string());
+        let res = parse_json_to_ctx("[[[]],[]]").unwrap();
+        match res {
+            CtxValue::List(list) => {
+                assert_eq!(list.len(), 2);
+                assert!(matches!(&list[0], CtxValue::List(inner) if inner.len() == 1));
+            }
+            _ => panic!("should be a list"),
+        }
     }
     #[test]
     pub fn brackets_in_key() {
-        let json_str = "{ \"}}}key\": 1 }".to_string();
-        let res = slice_until_closing_bracket(json_str.clone());
-        assert_eq!(res, json_str);
+        let res = parse_json_to_ctx("{ \"}}}key\": 1 }").unwrap();
+        assert!(matches!(
+            get(&res, "}}}key"),
+            CtxValue::Number(Number::Integer(1))
+        ));
     }
     #[test]
     pub fn couple_keys() {
-        let json_str = "{ \"k1\": \"value\", \"k2\": {} }".to_string();
-        let res = slice_until_closing_bracket(json_str.clone());
-        assert_eq!(res, json_str);
+        let res = parse_json_to_ctx("{ \"k1\": \"value\", \"k2\": {} }").unwrap();
+        assert!(matches!(get(&res, "k1"), CtxValue::String(s) if s == "value"));
+        assert!(matches!(get(&res, "k2"), CtxValue::HashMap(map) if map.is_empty()));
+    }
+    #[test]
+    pub fn escaped_quotes() {
+        let res = parse_json_to_ctx(include_str!("test.json")).unwrap();
+        assert!(matches!(get(&res, "key"), CtxValue::String(s) if s == "malicous value \" }\""));
+    }
+    #[test]
+    pub fn scalars() {
+        let res = parse_json_to_ctx(
+            "{\"f\": -1.5e2, \"i\": 9007199254740993, \"n\": null, \"b\": false, \"u\": \"\\u00e9\\ud83e\\udd80\"}",
+        )
+        .unwrap();
+        assert!(matches!(get(&res, "f"), CtxValue::Number(Number::Float(f)) if *f == -150.0));
+        assert!(matches!(
+            get(&res, "i"),
+            CtxValue::Number(Number::Integer(9007199254740993))
+        ));
+        assert!(matches!(get(&res, "n"), CtxValue::Null));
+        assert!(matches!(get(&res, "b"), CtxValue::Boolean(false)));
+        assert!(matches!(get(&res, "u"), CtxValue::String(s) if s == "é🦀"));
+    }
+    #[test]
+    pub fn error_position() {
+        let err = parse_json_to_ctx("{\n  \"a\": 1,\n  \"b\" 2\n}").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 7));
+        let err = parse_json_to_ctx("[01]").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 2));
+    }
+    #[test]
+    pub fn too_deep() {
+        let nested = |depth| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(parse_json_to_ctx(&nested(MAX_DEPTH)).is_ok());
+        let err = parse_json_to_ctx(&nested(MAX_DEPTH + 1)).unwrap_err();
+        assert_eq!(err.message, "Nested more than 128 levels deep");
+        assert_eq!((err.line, err.column), (1, MAX_DEPTH + 1));
+        // deep enough to overflow the stack without the limit
+        assert!(parse_json_to_ctx(&"{\"a\":".repeat(100_000)).is_err());
     }
 }
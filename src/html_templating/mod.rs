@@ -1,104 +1,264 @@
+#[cfg(test)]
 mod spec_tests;
 use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Arc;
 mod escape_html;
-mod json_to_ctx;
+mod html_context;
+pub mod helpers;
+pub mod json_to_ctx;
+mod parse_template;
+mod render_template;
+mod serde_ctx;
+mod template_error;
+use parse_template::Node;
+pub use template_error::{Location, TemplateError, TemplateErrorKind, UnresolvedName};
 // A mustache compliant templating engine 🚀
 
-struct TemplateEngine;
+/// Holds named templates, which can be rendered or pulled into other templates as partials and parents
+/// along with the helpers templates can call as sections
+pub struct TemplateEngine {
+    templates: HashMap<String, Template>,
+    helpers: HashMap<String, helpers::Helper>,
+    data_helpers: HashMap<String, helpers::DataHelper>,
+    /// Unknown partials, parents and names missing from the context are errors
+    /// instead of rendering as an empty string
+    strict: bool,
+    /// Off for templates that aren't html (IE: config files), {{name}} then renders like {{{name}}}
+    escape: bool,
+}
 
-trait OneoffRender {
-    fn oneoff_render(&self, template_string: String, context: HashMap<&str, CtxValue>) -> String;
+/// A registered template, parsed once when it's added
+struct Template {
+    source: String,
+    nodes: Vec<Node>,
+}
+
+impl Default for TemplateEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TemplateEngine {
+    /// Comes with the default helpers (date, slugify, truncate, markdown) registered
+    pub fn new() -> TemplateEngine {
+        TemplateEngine {
+            templates: HashMap::new(),
+            helpers: helpers::default_helpers(),
+            data_helpers: HashMap::new(),
+            strict: false,
+            escape: true,
+        }
+    }
+
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn set_escape(&mut self, escape: bool) {
+        self.escape = escape;
+    }
+
+    /// Registers a helper under name, replacing any helper already registered with it
+    /// values in the context take precedence over helpers with the same name
+    pub fn register_helper<F>(&mut self, name: &str, helper: F)
+    where
+        F: Fn(&str, &str) -> String + Send + Sync + 'static,
+    {
+        self.helpers.insert(name.to_string(), Arc::new(helper));
+    }
+
+    /// Registers a helper that hands back data for the section to render with, replacing any registered with name
+    /// values in the context take precedence over it too
+    pub fn register_data_helper<F>(&mut self, name: &str, helper: F)
+    where
+        F: Fn(&str) -> Option<CtxValue> + Send + Sync + 'static,
+    {
+        self.data_helpers.insert(name.to_string(), Arc::new(helper));
+    }
+
+    /// Whether a template is registered under name
+    pub fn has_template(&self, name: &str) -> bool {
+        self.templates.contains_key(name)
+    }
+
+    /// Registers a template under name, replacing any template already registered with it
+    /// Errors (without registering it) if the template doesn't parse
+    pub fn add_template(
+        &mut self,
+        name: &str,
+        template_string: String,
+    ) -> Result<(), TemplateError> {
+        let nodes = parse_template::parse_template(name, &template_string)?;
+        self.templates.insert(
+            name.to_string(),
+            Template {
+                source: template_string,
+                nodes,
+            },
+        );
+        Ok(())
+    }
+
+    /// Registers every *.mustache file in dir under it's file stem (IE: partials/header.mustache => "header")
+    pub fn add_templates_dir(&mut self, dir: &Path) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "mustache") {
+                let name = path.file_stem().unwrap().to_string_lossy().to_string();
+                self.add_template(&name, fs::read_to_string(&path)?)
+                    .map_err(io::Error::other)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders a registered template
+    pub fn render(&self, name: &str, context: &CtxValue) -> Result<String, TemplateError> {
+        let (result, _) = self.render_with_warnings(name, context)?;
+        Ok(result)
+    }
+
+    /// Same as render but also hands back every name that couldn't be resolved
+    /// (in strict mode those are an error so the warnings are always empty)
+    pub fn render_with_warnings(
+        &self,
+        name: &str,
+        context: &CtxValue,
+    ) -> Result<(String, Vec<UnresolvedName>), TemplateError> {
+        let mut buffer = vec![];
+        let unresolved = self.render_to(name, context, &mut buffer)?;
+        let result = String::from_utf8(buffer).expect("only strs are written");
+        Ok((result, unresolved))
+    }
+
+    /// Streams a registered template into writer (IE: a file or a TcpStream) without building it up in memory
+    /// writes are small and frequent so wrap unbuffered writers in a BufWriter
+    /// on an error some of the output may have already been written
+    pub fn render_to<W: Write>(
+        &self,
+        name: &str,
+        context: &CtxValue,
+        mut writer: W,
+    ) -> Result<Vec<UnresolvedName>, TemplateError> {
+        let template = self.templates.get(name).ok_or_else(|| TemplateError {
+            kind: TemplateErrorKind::UnknownTemplate(name.to_string()),
+            location: None,
+        })?;
+        self.render_nodes(&template.nodes, context, &mut writer)
+    }
+
+    fn render_nodes(
+        &self,
+        nodes: &[Node],
+        context: &CtxValue,
+        out: &mut dyn Write,
+    ) -> Result<Vec<UnresolvedName>, TemplateError> {
+        let mut unresolved = vec![];
+        render_template::render_nodes(
+            self,
+            &mut unresolved,
+            nodes,
+            &mut vec![context],
+            &HashMap::new(),
+            out,
+        )?;
+        if self.strict && !unresolved.is_empty() {
+            return Err(TemplateError {
+                kind: TemplateErrorKind::UnresolvedNames(unresolved),
+                location: None,
+            });
+        }
+        Ok(unresolved)
+    }
+}
+
+pub trait OneoffRender {
+    fn oneoff_render(
+        &self,
+        template_string: String,
+        context: HashMap<&str, CtxValue>,
+    ) -> Result<String, TemplateError>;
 }
 
 impl OneoffRender for TemplateEngine {
-    fn oneoff_render(&self, template_string: String, context: HashMap<&str, CtxValue>) -> String {
-        mustachify(template_string, context)
+    fn oneoff_render(
+        &self,
+        template_string: String,
+        context: HashMap<&str, CtxValue>,
+    ) -> Result<String, TemplateError> {
+        let context = CtxValue::HashMap(Box::new(
+            context
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        ));
+        let nodes = parse_template::parse_template("oneoff", &template_string)?;
+        let mut buffer = vec![];
+        self.render_nodes(&nodes, &context, &mut buffer)?;
+        Ok(String::from_utf8(buffer).expect("only strs are written"))
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub enum CtxValue {
     String(String),
     Boolean(bool),
-    Number(i8),
+    Number(Number),
+    Null,
     HashMap(Box<HashMap<String, CtxValue>>),
     List(Box<Vec<CtxValue>>),
+    /// Called with the raw section text (empty for interpolation), what it returns is rendered as a template
+    Lambda(Arc<dyn Fn(&str) -> String + Send + Sync>),
 }
 
-pub fn mustachify(template_string: String, context: HashMap<&str, CtxValue>) -> String {
-    let mut result = String::new();
-    let mut char_iter = template_string.chars();
-
-    while let Some(char) = char_iter.next() {
-        let mut sub_iter = char_iter.clone();
-        if char == '{' && sub_iter.next().is_some_and(|next_char| next_char == '{') {
-            // check for three in a rowwww and ampersand
-            let is_triple_stache = sub_iter.clone().next().is_some_and(|char| char == '{');
-            let is_html_escaped =
-                !(is_triple_stache || sub_iter.clone().next().is_some_and(|char| char == '&'));
-            // closing tags?
-            let closing_pattern = if is_triple_stache { "}}}" } else { "}}" };
-            // rest_of_line after the first {
-            let rest_of_line: String = char_iter.clone().collect();
-            if let Some(closing_stache_byte_idx) = rest_of_line.find(closing_pattern) {
-                // if we have found this then char_iter is on the first { sub_iter is on the second {
-                let skip = if is_triple_stache { 3 } else { 2 };
-                // rest_of_line includes a starting { or {{ so - (skip-1)
-                let char_len_until_end_of_closing_brackets =
-                    rest_of_line[..closing_stache_byte_idx].chars().count() + skip;
-                let mut content_in_stache: String = rest_of_line[..closing_stache_byte_idx]
-                    .chars()
-                    .skip(skip - 1)
-                    .collect();
-                if content_in_stache
-                    .chars()
-                    .next()
-                    .is_some_and(|char| char == '&')
-                {
-                    content_in_stache = content_in_stache
-                        .strip_prefix("&")
-                        .expect("should be there")
-                        .to_string();
-                }
-                // here's where we start thinking about all the different stache statements
-                if content_in_stache.starts_with("#") {
-                    // Section!
-                } else if content_in_stache.starts_with("^") {
-                    // Inverted Section!
-                } else if content_in_stache.starts_with(">") {
-                    // Partial!
-                } else if content_in_stache.starts_with("!") {
-                    // Comment!
-                } else if content_in_stache.starts_with("$") {
-                    // Block!
-                } else {
-                    // regular variable
-                    let key = content_in_stache.trim();
-                    let variable_value = match context.get(key) {
-                        Some(val) => val.clone(),
-                        None => CtxValue::String("".to_string()),
-                    };
-                    // makes sense for num and string but boolean?? this is smelly
-                    let mut string_value = match variable_value {
-                        CtxValue::Boolean(_) => "".to_string(),
-                        CtxValue::Number(n) => n.to_string(),
-                        CtxValue::String(s) => s,
-                        _ => { todo!()}
-                    };
-                    if is_html_escaped {
-                        string_value = escape_html::escape_html(string_value);
-                    }
-                    result.push_str(&string_value);
-                    char_iter.nth(char_len_until_end_of_closing_brackets - 1);
-                }
-            } else {
-                result.push(char);
-            }
-        } else {
-            result.push(char);
+impl CtxValue {
+    pub fn lambda<F>(f: F) -> CtxValue
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        CtxValue::Lambda(Arc::new(f))
+    }
+}
+
+impl fmt::Debug for CtxValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CtxValue::String(s) => f.debug_tuple("String").field(s).finish(),
+            CtxValue::Boolean(b) => f.debug_tuple("Boolean").field(b).finish(),
+            CtxValue::Number(n) => f.debug_tuple("Number").field(n).finish(),
+            CtxValue::Null => f.write_str("Null"),
+            CtxValue::HashMap(map) => f.debug_tuple("HashMap").field(map).finish(),
+            CtxValue::List(list) => f.debug_tuple("List").field(list).finish(),
+            CtxValue::Lambda(_) => f.write_str("Lambda"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Number {
+    Integer(i64),
+    Float(f64),
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::Integer(i) => write!(f, "{i}"),
+            Number::Float(float) => write!(f, "{float}"),
         }
     }
-    return result;
+}
+
+pub fn mustachify(
+    template_string: String,
+    context: HashMap<&str, CtxValue>,
+) -> Result<String, TemplateError> {
+    TemplateEngine::new().oneoff_render(template_string, context)
 }
 
 #[cfg(test)]
@@ -108,7 +268,7 @@ mod tests {
     #[test]
     pub fn empty_var() {
         let hash: HashMap<&str, CtxValue> = HashMap::new();
-        let stache = mustachify("some text {{ stache }} some text".to_string(), hash);
+        let stache = mustachify("some text {{ stache }} some text".to_string(), hash).unwrap();
         assert_eq!(stache, "some text  some text".to_string())
     }
 
@@ -116,7 +276,278 @@ mod tests {
     pub fn with_var() {
         let mut hash: HashMap<&str, CtxValue> = HashMap::new();
         hash.insert("stache", CtxValue::String("world".to_string()));
-        let res = mustachify("hello {{ stache }} \n".to_string(), hash);
+        let res = mustachify("hello {{ stache }} \n".to_string(), hash).unwrap();
         assert_eq!(res, "hello world \n".to_string())
     }
+
+    #[test]
+    pub fn parent_with_block_overrides() {
+        let mut engine = TemplateEngine::new();
+        engine.add_template(
+            "base",
+            "<title>{{$title}}{{/title}}{{ title }}</title>\n<body>\n  {{$content}}\n  {{/content}}\n</body>".to_string(),
+        )
+        .unwrap();
+        engine.add_template(
+            "content",
+            "{{<base}}\n{{$title}}{{ content_title }} - {{/title}}\n{{$content}}\n<p>{{ content }}</p>\n{{/content}}\n{{/base}}".to_string(),
+        )
+        .unwrap();
+        let mut ctx = HashMap::new();
+        ctx.insert(
+            "title".to_string(),
+            CtxValue::String("megalopa".to_string()),
+        );
+        ctx.insert(
+            "content_title".to_string(),
+            CtxValue::String("Post".to_string()),
+        );
+        ctx.insert("content".to_string(), CtxValue::String("hi".to_string()));
+        let res = engine.render("content", &CtxValue::HashMap(Box::new(ctx)));
+        assert_eq!(
+            res.unwrap(),
+            "<title>Post - megalopa</title>\n<body>\n  <p>hi</p>\n</body>".to_string()
+        )
+    }
+
+    #[test]
+    pub fn block_defaults_without_override() {
+        let mut engine = TemplateEngine::new();
+        engine
+            .add_template(
+                "base",
+                "{{$greeting}}hello{{/greeting}} {{$name}}you{{/name}}".to_string(),
+            )
+            .unwrap();
+        let res = engine
+            .oneoff_render(
+                "{{<base}}{{$name}}world{{/name}}{{/base}}".to_string(),
+                HashMap::new(),
+            )
+            .unwrap();
+        assert_eq!(res, "hello world".to_string())
+    }
+
+    #[test]
+    pub fn outermost_override_wins() {
+        let mut engine = TemplateEngine::new();
+        engine
+            .add_template("grandparent", "{{$a}}grandparent{{/a}}".to_string())
+            .unwrap();
+        engine
+            .add_template(
+                "parent",
+                "{{<grandparent}}{{$a}}parent{{/a}}{{/grandparent}}".to_string(),
+            )
+            .unwrap();
+        let res = engine
+            .oneoff_render(
+                "{{<parent}}{{$a}}child{{/a}}{{/parent}}".to_string(),
+                HashMap::new(),
+            )
+            .unwrap();
+        assert_eq!(res, "child".to_string())
+    }
+
+    #[test]
+    pub fn section_lambda_gets_raw_text() {
+        let mut hash: HashMap<&str, CtxValue> = HashMap::new();
+        hash.insert("name", CtxValue::String("world".to_string()));
+        hash.insert("bold", CtxValue::lambda(|text| format!("<b>{text}</b>")));
+        let res = mustachify("{{#bold}}hi {{name}}{{/bold}}".to_string(), hash).unwrap();
+        assert_eq!(res, "<b>hi world</b>".to_string())
+    }
+
+    #[test]
+    pub fn helpers_get_rendered_content() {
+        let mut engine = TemplateEngine::new();
+        engine.register_helper("shout", |content, _| content.to_uppercase());
+        let mut hash: HashMap<&str, CtxValue> = HashMap::new();
+        hash.insert("title", CtxValue::String("Hello World".to_string()));
+        let res = engine
+            .oneoff_render(
+                "{{#shout}}{{title}}{{/shout}} /{{#slugify}}{{title}}{{/slugify}}".to_string(),
+                hash,
+            )
+            .unwrap();
+        assert_eq!(res, "HELLO WORLD /hello-world".to_string())
+    }
+
+    #[test]
+    pub fn markdown_helper_content_is_only_escaped_once() {
+        let mut hash: HashMap<&str, CtxValue> = HashMap::new();
+        hash.insert("body", CtxValue::String("a & b, `<tag>`".to_string()));
+        hash.insert(
+            "description",
+            CtxValue::String("<script>alert(1)</script>".to_string()),
+        );
+        let res = mustachify(
+            "{{#markdown}}{{ body }}{{/markdown}}{{#markdown}}{{ description }}{{/markdown}}"
+                .to_string(),
+            hash,
+        )
+        .unwrap();
+        assert_eq!(
+            res,
+            "<div><div>a &amp; b, <code>&lt;tag&gt;</code></div></div><div><div>&lt;script&gt;alert(1)&lt;/script&gt;</div></div>"
+        );
+    }
+
+    #[test]
+    pub fn data_helpers_render_the_section_with_what_they_find() {
+        let mut engine = TemplateEngine::new();
+        engine.register_data_helper("get_numbers", |argument| match argument {
+            "small" => Some(CtxValue::List(Box::new(vec![
+                CtxValue::Number(Number::Integer(1)),
+                CtxValue::Number(Number::Integer(2)),
+            ]))),
+            _ => None,
+        });
+        let mut hash: HashMap<&str, CtxValue> = HashMap::new();
+        hash.insert("title", CtxValue::String("Numbers".to_string()));
+        let res = engine
+            .oneoff_render(
+                "{{#get_numbers small}}{{title}} {{.}},{{/get_numbers}}{{#get_numbers big}}none{{/get_numbers}}"
+                    .to_string(),
+                hash,
+            )
+            .unwrap();
+        assert_eq!(res, "Numbers 1,Numbers 2,".to_string())
+    }
+
+    #[test]
+    pub fn unknown_partial_only_errors_in_strict_mode() {
+        let mut engine = TemplateEngine::new();
+        engine
+            .add_template("page", "<nav>\n{{> header}}</nav>".to_string())
+            .unwrap();
+        let ctx = CtxValue::HashMap(Box::default());
+        assert_eq!(engine.render("page", &ctx).unwrap(), "<nav>\n</nav>");
+
+        engine.set_strict(true);
+        let err = engine.render("page", &ctx).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown partial \"header\" in template \"page\" at line 2, column 1\n  |\n2 | {{> header}}</nav>\n  | ^^^^^^^^^^^^"
+        );
+    }
+
+    #[test]
+    pub fn broken_templates_are_not_registered() {
+        let mut engine = TemplateEngine::new();
+        let err = engine
+            .add_template("page", "{{#posts}}{{/post}}".to_string())
+            .unwrap_err();
+        assert!(matches!(
+            err.kind,
+            TemplateErrorKind::MismatchedClosingTag { .. }
+        ));
+        assert!(matches!(
+            engine.render("page", &CtxValue::Null).unwrap_err().kind,
+            TemplateErrorKind::UnknownTemplate(_)
+        ));
+    }
+
+    #[test]
+    pub fn unresolved_names() {
+        let mut engine = TemplateEngine::new();
+        engine
+            .add_template(
+                "content",
+                "<h1>{{ title }}</h1>\n{{{ contnet }}}{{#tags}}{{.}}{{/tags}}{{^tags}}-{{/tags}}"
+                    .to_string(),
+            )
+            .unwrap();
+        let mut ctx = HashMap::new();
+        ctx.insert("title".to_string(), CtxValue::String("Hi".to_string()));
+        let ctx = CtxValue::HashMap(Box::new(ctx));
+
+        let (res, warnings) = engine.render_with_warnings("content", &ctx).unwrap();
+        assert_eq!(res, "<h1>Hi</h1>\n-");
+        // inverted sections are meant for missing names so they aren't reported
+        let names: Vec<_> = warnings
+            .iter()
+            .map(|w| (w.name.as_str(), w.location.line, w.location.column))
+            .collect();
+        assert_eq!(names, vec![("contnet", 2, 1), ("tags", 2, 16)]);
+
+        engine.set_strict(true);
+        let err = engine.render("content", &ctx).unwrap_err();
+        assert!(err.to_string().starts_with(
+            "2 unresolved name(s)\n\nUnresolved name \"contnet\" in template \"content\" at line 2, column 1\n"
+        ));
+    }
+
+    #[test]
+    pub fn escaping_follows_the_html_context() {
+        let mut hash: HashMap<&str, CtxValue> = HashMap::new();
+        hash.insert("title", CtxValue::String("\"><script>".to_string()));
+        hash.insert("path", CtxValue::String("javascript:alert(1)".to_string()));
+        let res = mustachify(
+            "<a href=\"{{ path }}\" title={{ title }}>{{ title }}</a><script>let t = \"{{ title }}\";</script>"
+                .to_string(),
+            hash,
+        )
+        .unwrap();
+        assert_eq!(
+            res,
+            "<a href=\"#unsafe-url\" title=&quot;&gt;&lt;script&gt;>&quot;&gt;&lt;script&gt;</a><script>let t = \"\\u0022\\u003E\\u003Cscript\\u003E\";</script>"
+        );
+    }
+
+    #[test]
+    pub fn srcsets_keep_their_descriptors() {
+        let mut hash: HashMap<&str, CtxValue> = HashMap::new();
+        hash.insert("srcset", CtxValue::String("a.png 1x, b.png 2x".to_string()));
+        hash.insert("url", CtxValue::String("javascript:alert(1)".to_string()));
+        let res = mustachify(
+            "<img srcset=\"{{ srcset }}\"><img srcset=\"a.png 1x, {{ url }} 2x\">".to_string(),
+            hash,
+        )
+        .unwrap();
+        assert_eq!(
+            res,
+            "<img srcset=\"a.png 1x, b.png 2x\"><img srcset=\"a.png 1x, #unsafe-url 2x\">"
+        );
+    }
+
+    #[test]
+    pub fn values_outside_js_strings_are_js_literals() {
+        let mut hash: HashMap<&str, CtxValue> = HashMap::new();
+        hash.insert("count", CtxValue::Number(Number::Integer(3)));
+        hash.insert("title", CtxValue::String("a\"; alert(1); \"".to_string()));
+        let res = mustachify(
+            "<script>var n = {{count}}; var t = {{title}}; var q = '{{title}}';</script><button onclick=\"go({{title}})\">"
+                .to_string(),
+            hash,
+        )
+        .unwrap();
+        assert_eq!(
+            res,
+            "<script>var n = 3; var t = \"a\\u0022; alert(1); \\u0022\"; var q = 'a\\u0022; alert(1); \\u0022';</script><button onclick=\"go(&quot;a\\u0022; alert(1); \\u0022&quot;)\">"
+        );
+    }
+
+    #[test]
+    pub fn render_to_streams_into_a_writer() {
+        let mut engine = TemplateEngine::new();
+        engine
+            .add_template("list", "{{#items}}<li>{{.}}</li>{{/items}}".to_string())
+            .unwrap();
+        let items = (0..3)
+            .map(|i| CtxValue::Number(Number::Integer(i)))
+            .collect();
+        let mut ctx = HashMap::new();
+        ctx.insert("items".to_string(), CtxValue::List(Box::new(items)));
+        let ctx = CtxValue::HashMap(Box::new(ctx));
+
+        let mut out = vec![];
+        engine.render_to("list", &ctx, &mut out).unwrap();
+        assert_eq!(out, b"<li>0</li><li>1</li><li>2</li>");
+
+        // a writer with room for only part of the output
+        let mut buffer = [0u8; 8];
+        let err = engine.render_to("list", &ctx, &mut buffer[..]).unwrap_err();
+        assert!(matches!(err.kind, TemplateErrorKind::Write(_)));
+    }
 }
@@ -0,0 +1,538 @@
+use super::html_context::{HtmlContext, HtmlContextTracker};
+use super::template_error::{Location, TemplateError, TemplateErrorKind};
+
+/// A parsed piece of a mustache template
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    Text(String),
+    Variable {
+        name: String,
+        is_html_escaped: bool,
+        /// where the tag sits in the html, picks how the value gets escaped
+        html_context: HtmlContext,
+        location: Location,
+    },
+    /// raw is the unparsed text between the tags, handed to lambdas along with the delimiters it was written with
+    Section {
+        name: String,
+        is_inverted: bool,
+        children: Vec<Node>,
+        raw: String,
+        delimiters: (String, String),
+        location: Location,
+    },
+    Partial {
+        name: String,
+        indentation: String,
+        location: Location,
+    },
+    /// `{{<parent}}` - only the blocks inside it are kept, everything else is ignored
+    Parent {
+        name: String,
+        indentation: String,
+        children: Vec<Node>,
+        location: Location,
+    },
+    /// `{{$block}}` - indentation is only set when the opening tag is standalone
+    Block {
+        name: String,
+        indentation: Option<String>,
+        children: Vec<Node>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum TagKind {
+    Variable { is_html_escaped: bool },
+    Section,
+    InvertedSection,
+    Close,
+    Comment,
+    Partial,
+    Delimiters(String, String),
+    Parent,
+    Block,
+}
+
+impl TagKind {
+    /// Tags that don't output anything can sit on their own line and take the whole line with them
+    fn can_be_standalone(&self) -> bool {
+        !matches!(self, TagKind::Variable { .. })
+    }
+    fn is_inheritance(&self) -> bool {
+        matches!(self, TagKind::Parent | TagKind::Block)
+    }
+}
+
+#[derive(Debug)]
+struct Tag {
+    kind: TagKind,
+    name: String,
+    /// byte idx of the opening delimiter
+    start: usize,
+    /// byte idx right after the closing delimiter
+    end: usize,
+}
+
+/// A section/parent/block that hasn't seen it's closing tag yet
+struct OpenNode {
+    kind: TagKind,
+    name: String,
+    indentation: String,
+    is_standalone: bool,
+    children: Vec<Node>,
+    /// byte idx right after the opening tag
+    raw_start: usize,
+    delimiters: (String, String),
+    location: Location,
+}
+
+impl OpenNode {
+    fn into_node(self, raw: &str) -> Node {
+        match self.kind {
+            TagKind::InvertedSection => Node::Section {
+                name: self.name,
+                is_inverted: true,
+                children: self.children,
+                raw: raw.to_string(),
+                delimiters: self.delimiters,
+                location: self.location,
+            },
+            TagKind::Parent => Node::Parent {
+                name: self.name,
+                indentation: self.indentation,
+                children: self
+                    .children
+                    .into_iter()
+                    .filter(|child| matches!(child, Node::Block { .. }))
+                    .collect(),
+                location: self.location,
+            },
+            TagKind::Block => {
+                // a standalone block's overrides are indented like it's default content (if it has any)
+                let intrinsic_indentation = raw
+                    .lines()
+                    .find(|line| !line.trim().is_empty())
+                    .map(|line| {
+                        line.chars()
+                            .take_while(|c| *c == ' ' || *c == '\t')
+                            .collect::<String>()
+                    });
+                Node::Block {
+                    name: self.name,
+                    indentation: self
+                        .is_standalone
+                        .then(|| intrinsic_indentation.unwrap_or(self.indentation)),
+                    children: self.children,
+                }
+            }
+            _ => Node::Section {
+                name: self.name,
+                is_inverted: false,
+                children: self.children,
+                raw: raw.to_string(),
+                delimiters: self.delimiters,
+                location: self.location,
+            },
+        }
+    }
+}
+
+/// Parses a template string into a tree of nodes
+/// Standalone tags (alone on their line) are stripped along with their line's whitespace
+pub fn parse_template(template_name: &str, template: &str) -> Result<Vec<Node>, TemplateError> {
+    parse_template_with_delimiters(template_name, template, "{{", "}}")
+}
+
+/// Same as parse_template but starting with custom delimiters (lambdas render with the section's delimiters)
+pub fn parse_template_with_delimiters(
+    template_name: &str,
+    template: &str,
+    open_delimiter: &str,
+    close_delimiter: &str,
+) -> Result<Vec<Node>, TemplateError> {
+    let location = |start: usize, end: usize| Location::new(template_name, template, start, end);
+    let mut open_delimiter = open_delimiter.to_string();
+    let mut close_delimiter = close_delimiter.to_string();
+    // the root of the stack is never closed
+    let mut stack = vec![OpenNode {
+        kind: TagKind::Section,
+        name: String::new(),
+        indentation: String::new(),
+        is_standalone: false,
+        children: vec![],
+        raw_start: 0,
+        delimiters: (open_delimiter.clone(), close_delimiter.clone()),
+        location: location(0, 0),
+    }];
+    let mut text = String::new();
+    let mut idx = 0;
+    let mut html_context = HtmlContextTracker::new();
+
+    while idx < template.len() {
+        let Some(tag_offset) = template[idx..].find(&open_delimiter) else {
+            text.push_str(&template[idx..]);
+            break;
+        };
+        let tag_start = idx + tag_offset;
+        text.push_str(&template[idx..tag_start]);
+        html_context.feed(&template[idx..tag_start]);
+        let tag = read_tag(template, tag_start, &open_delimiter, &close_delimiter)
+            .expect("template has the opening delimiter at tag_start")
+            .map_err(|kind| {
+                let end = tag_start + open_delimiter.len();
+                TemplateError::new(kind, location(tag_start, end))
+            })?;
+
+        // tags of the inheritance extension can share a line and still be standalone
+        // IE: "  {{$block}}{{/block}}"
+        let mut run = vec![tag];
+        if run[0].kind.can_be_standalone() {
+            while let Some(Ok(next_tag)) = read_tag(
+                template,
+                run[run.len() - 1].end,
+                &open_delimiter,
+                &close_delimiter,
+            ) {
+                if next_tag.kind.is_inheritance() || next_tag.kind == TagKind::Close {
+                    run.push(next_tag);
+                } else {
+                    break;
+                }
+            }
+            if run.len() > 1 && !is_inheritance_run(&run, &stack) {
+                run.truncate(1);
+            }
+        }
+
+        let run_start = run[0].start;
+        let run_end = run[run.len() - 1].end;
+        idx = run_end;
+        let mut indentation = String::new();
+        let mut is_standalone = false;
+        if run[0].kind.can_be_standalone() {
+            if let Some(line_end) = standalone_line_end(template, run_start, run_end) {
+                let line_start = template[..run_start].rfind('\n').map_or(0, |i| i + 1);
+                indentation = template[line_start..run_start].to_string();
+                text.truncate(text.len() - indentation.len());
+                is_standalone = true;
+                idx = line_end;
+            }
+        }
+
+        for tag in run {
+            match tag.kind {
+                TagKind::Comment => {}
+                TagKind::Delimiters(open, close) => {
+                    open_delimiter = open;
+                    close_delimiter = close;
+                }
+                TagKind::Variable { is_html_escaped } => {
+                    flush_text(&mut text, &mut stack);
+                    push_node(
+                        &mut stack,
+                        Node::Variable {
+                            name: tag.name,
+                            is_html_escaped,
+                            html_context: html_context.context(),
+                            location: location(tag.start, tag.end),
+                        },
+                    );
+                    html_context.interpolated();
+                }
+                // partials are parsed on their own, starting in Text, not in the context they're included in
+                TagKind::Partial => {
+                    flush_text(&mut text, &mut stack);
+                    push_node(
+                        &mut stack,
+                        Node::Partial {
+                            name: tag.name,
+                            indentation: indentation.clone(),
+                            location: location(tag.start, tag.end),
+                        },
+                    );
+                }
+                TagKind::Section | TagKind::InvertedSection | TagKind::Parent | TagKind::Block => {
+                    flush_text(&mut text, &mut stack);
+                    stack.push(OpenNode {
+                        kind: tag.kind,
+                        name: tag.name,
+                        indentation: indentation.clone(),
+                        is_standalone,
+                        children: vec![],
+                        raw_start: tag.end,
+                        delimiters: (open_delimiter.clone(), close_delimiter.clone()),
+                        location: location(tag.start, tag.end),
+                    });
+                }
+                TagKind::Close => {
+                    flush_text(&mut text, &mut stack);
+                    if stack.len() == 1 {
+                        return Err(TemplateError::new(
+                            TemplateErrorKind::UnopenedClosingTag(tag.name),
+                            location(tag.start, tag.end),
+                        ));
+                    }
+                    let open_node = stack.pop().expect("stack has more than the root");
+                    // helpers take an argument after their name IE: {{#truncate 20}}...{{/truncate}}
+                    let open_name = open_node.name.split_whitespace().next().unwrap_or("");
+                    if open_name != tag.name {
+                        return Err(TemplateError::new(
+                            TemplateErrorKind::MismatchedClosingTag {
+                                expected: open_name.to_string(),
+                                found: tag.name,
+                            },
+                            location(tag.start, tag.end),
+                        ));
+                    }
+                    let raw = &template[open_node.raw_start..tag.start];
+                    push_node(&mut stack, open_node.into_node(raw));
+                }
+            }
+        }
+    }
+    flush_text(&mut text, &mut stack);
+
+    if stack.len() > 1 {
+        let open_node = stack.pop().expect("stack has more than the root");
+        return Err(TemplateError::new(
+            TemplateErrorKind::UnclosedSection(open_node.name),
+            open_node.location,
+        ));
+    }
+    Ok(stack.pop().expect("root is always on the stack").children)
+}
+
+fn flush_text(text: &mut String, stack: &mut [OpenNode]) {
+    if !text.is_empty() {
+        push_node(stack, Node::Text(std::mem::take(text)));
+    }
+}
+
+fn push_node(stack: &mut [OpenNode], node: Node) {
+    stack
+        .last_mut()
+        .expect("root is always on the stack")
+        .children
+        .push(node);
+}
+
+/// A run of tags is only treated as one unit if every closing tag closes a parent or block
+fn is_inheritance_run(run: &[Tag], stack: &[OpenNode]) -> bool {
+    let mut open_kinds: Vec<&TagKind> = stack.iter().skip(1).map(|node| &node.kind).collect();
+    for tag in run {
+        if tag.kind == TagKind::Close {
+            match open_kinds.pop() {
+                Some(kind) if kind.is_inheritance() => {}
+                _ => return false,
+            }
+        } else {
+            open_kinds.push(&tag.kind);
+        }
+    }
+    true
+}
+
+/// If only whitespace surrounds the tag(s) on their line
+/// returns the byte idx the template should continue from (after the line ending)
+fn standalone_line_end(template: &str, start: usize, end: usize) -> Option<usize> {
+    let line_start = template[..start].rfind('\n').map_or(0, |i| i + 1);
+    let before = &template[line_start..start];
+    if !before.chars().all(|c| c == ' ' || c == '\t') {
+        return None;
+    }
+    let after = &template[end..];
+    let (rest_of_line, line_end) = match after.find('\n') {
+        Some(newline_idx) => (&after[..newline_idx], end + newline_idx + 1),
+        None => (after, template.len()),
+    };
+    let rest_of_line = rest_of_line.strip_suffix('\r').unwrap_or(rest_of_line);
+    if rest_of_line.chars().all(|c| c == ' ' || c == '\t') {
+        Some(line_end)
+    } else {
+        None
+    }
+}
+
+/// Reads the tag starting at start (if there is one)
+fn read_tag(
+    template: &str,
+    start: usize,
+    open_delimiter: &str,
+    close_delimiter: &str,
+) -> Option<Result<Tag, TemplateErrorKind>> {
+    if !template[start..].starts_with(open_delimiter) {
+        return None;
+    }
+    Some(read_tag_content(
+        template,
+        start,
+        open_delimiter,
+        close_delimiter,
+    ))
+}
+
+fn read_tag_content(
+    template: &str,
+    start: usize,
+    open_delimiter: &str,
+    close_delimiter: &str,
+) -> Result<Tag, TemplateErrorKind> {
+    let content_start = start + open_delimiter.len();
+    let rest = &template[content_start..];
+
+    if let Some(triple_rest) = rest.strip_prefix('{') {
+        // {{{ unescaped }}}
+        let closing_pattern = format!("}}{close_delimiter}");
+        let closing_idx = triple_rest
+            .find(&closing_pattern)
+            .ok_or(TemplateErrorKind::UnclosedTag)?;
+        return Ok(Tag {
+            kind: TagKind::Variable {
+                is_html_escaped: false,
+            },
+            name: triple_rest[..closing_idx].trim().to_string(),
+            start,
+            end: content_start + 1 + closing_idx + closing_pattern.len(),
+        });
+    }
+
+    let closing_idx = rest
+        .find(close_delimiter)
+        .ok_or(TemplateErrorKind::UnclosedTag)?;
+    let content = rest[..closing_idx].trim();
+    let end = content_start + closing_idx + close_delimiter.len();
+    let mut chars = content.chars();
+    let (kind, name) = match chars.next() {
+        Some('#') => (TagKind::Section, chars.as_str()),
+        Some('^') => (TagKind::InvertedSection, chars.as_str()),
+        Some('/') => (TagKind::Close, chars.as_str()),
+        Some('!') => (TagKind::Comment, ""),
+        Some('>') => (TagKind::Partial, chars.as_str()),
+        Some('<') => (TagKind::Parent, chars.as_str()),
+        Some('$') => (TagKind::Block, chars.as_str()),
+        Some('&') => (
+            TagKind::Variable {
+                is_html_escaped: false,
+            },
+            chars.as_str(),
+        ),
+        Some('=') => {
+            // {{=<% %>=}}
+            let delimiters = chars
+                .as_str()
+                .strip_suffix('=')
+                .ok_or(TemplateErrorKind::InvalidDelimiters)?;
+            let delimiters: Vec<&str> = delimiters.split_whitespace().collect();
+            let [open, close] = delimiters[..] else {
+                return Err(TemplateErrorKind::InvalidDelimiters);
+            };
+            (TagKind::Delimiters(open.to_string(), close.to_string()), "")
+        }
+        _ => (
+            TagKind::Variable {
+                is_html_escaped: true,
+            },
+            content,
+        ),
+    };
+    Ok(Tag {
+        kind,
+        name: name.trim().to_string(),
+        start,
+        end,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn standalone_section_lines() {
+        let nodes = parse_template("test", "{{#a}}\n  hi\n{{/a}}\n").unwrap();
+        assert_eq!(
+            nodes,
+            vec![Node::Section {
+                name: "a".to_string(),
+                is_inverted: false,
+                children: vec![Node::Text("  hi\n".to_string())],
+                raw: "\n  hi\n".to_string(),
+                delimiters: ("{{".to_string(), "}}".to_string()),
+                location: Location {
+                    template_name: "test".to_string(),
+                    line: 1,
+                    column: 1,
+                    source_line: "{{#a}}".to_string(),
+                    len: 6,
+                },
+            }]
+        )
+    }
+
+    #[test]
+    pub fn parent_with_blocks() {
+        let nodes =
+            parse_template("test", "{{<base}}\n  {{$title}}Hi{{/title}}\n{{/base}}").unwrap();
+        let Node::Parent { name, children, .. } = &nodes[0] else {
+            panic!("should be a parent")
+        };
+        assert_eq!(name, "base");
+        assert_eq!(
+            children,
+            &vec![Node::Block {
+                name: "title".to_string(),
+                indentation: None,
+                children: vec![Node::Text("Hi".to_string())],
+            }]
+        )
+    }
+
+    #[test]
+    pub fn standalone_inline_block() {
+        let nodes = parse_template("test", "Hi,\n  {{$block}}{{/block}}\n").unwrap();
+        assert_eq!(
+            nodes,
+            vec![
+                Node::Text("Hi,\n".to_string()),
+                Node::Block {
+                    name: "block".to_string(),
+                    indentation: Some("  ".to_string()),
+                    children: vec![],
+                }
+            ]
+        )
+    }
+
+    #[test]
+    pub fn unclosed_section() {
+        let err =
+            parse_template("test", "<ul>\n  {{#posts}}<li>{{title}}</li>\n</ul>").unwrap_err();
+        assert_eq!(
+            err.kind,
+            TemplateErrorKind::UnclosedSection("posts".to_string())
+        );
+        let location = err.location.unwrap();
+        assert_eq!((location.line, location.column, location.len), (2, 3, 10));
+    }
+
+    #[test]
+    pub fn mismatched_closing_tag() {
+        let err = parse_template("test", "{{#a}}{{#b}}{{/a}}{{/b}}").unwrap_err();
+        assert_eq!(
+            err.kind,
+            TemplateErrorKind::MismatchedClosingTag {
+                expected: "b".to_string(),
+                found: "a".to_string()
+            }
+        );
+        assert_eq!(err.location.unwrap().column, 13);
+    }
+
+    #[test]
+    pub fn missing_closing_delimiter() {
+        let err = parse_template("test", "hello {{ name }").unwrap_err();
+        assert_eq!(err.kind, TemplateErrorKind::UnclosedTag);
+        assert_eq!(err.location.unwrap().column, 7);
+    }
+}
@@ -0,0 +1,352 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Write;
+
+use super::parse_template::{parse_template, parse_template_with_delimiters, Node};
+use super::template_error::{Location, TemplateError, TemplateErrorKind, UnresolvedName};
+use super::{CtxValue, TemplateEngine};
+
+/// block name => the nodes overriding the block's default content
+type Blocks = HashMap<String, Vec<Node>>;
+
+/// Walks the node tree writing output into out
+/// the context stack grows as we step into sections (the last value is the innermost)
+/// names missing from the context are collected in unresolved and render as nothing
+pub fn render_nodes(
+    engine: &TemplateEngine,
+    unresolved: &mut Vec<UnresolvedName>,
+    nodes: &[Node],
+    stack: &mut Vec<&CtxValue>,
+    blocks: &Blocks,
+    out: &mut dyn Write,
+) -> Result<(), TemplateError> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.write_all(text.as_bytes())?,
+            Node::Variable {
+                name,
+                is_html_escaped,
+                html_context,
+                location,
+            } => {
+                if let Some(value) = lookup(stack, name) {
+                    let is_escaped = *is_html_escaped && engine.escape;
+                    let output = match value {
+                        CtxValue::Lambda(lambda) => {
+                            // the lambda's return is rendered as a template (with default delimiters)
+                            let lambda_nodes = parse_template("lambda", &lambda(""))?;
+                            let string_value =
+                                render_to_string(engine, unresolved, &lambda_nodes, stack, blocks)?;
+                            if is_escaped {
+                                html_context.escape(string_value)
+                            } else {
+                                string_value
+                            }
+                        }
+                        _ if is_escaped => html_context.escape_value(value),
+                        _ => interpolate(value),
+                    };
+                    out.write_all(output.as_bytes())?;
+                } else {
+                    unresolved.push(UnresolvedName {
+                        name: name.clone(),
+                        location: location.clone(),
+                    });
+                }
+            }
+            Node::Section {
+                name,
+                is_inverted: true,
+                children,
+                ..
+            } => {
+                if !is_truthy(lookup(stack, name)) {
+                    render_nodes(engine, unresolved, children, stack, blocks, out)?;
+                }
+            }
+            Node::Section {
+                name,
+                is_inverted: false,
+                children,
+                raw,
+                delimiters,
+                location,
+            } => match lookup(stack, name) {
+                Some(CtxValue::Lambda(lambda)) => {
+                    let lambda_nodes = parse_template_with_delimiters(
+                        "lambda",
+                        &lambda(raw),
+                        &delimiters.0,
+                        &delimiters.1,
+                    )?;
+                    render_nodes(engine, unresolved, &lambda_nodes, stack, blocks, out)?;
+                }
+                Some(CtxValue::List(items)) => {
+                    for item in items.iter() {
+                        stack.push(item);
+                        let rendered =
+                            render_nodes(engine, unresolved, children, stack, blocks, out);
+                        stack.pop();
+                        rendered?;
+                    }
+                }
+                Some(value) if is_truthy(Some(value)) => {
+                    stack.push(value);
+                    let rendered = render_nodes(engine, unresolved, children, stack, blocks, out);
+                    stack.pop();
+                    rendered?;
+                }
+                Some(_) => {}
+                None => {
+                    // anything after the helper's name is passed along as it's argument
+                    let (helper_name, argument) = name.split_once(' ').unwrap_or((name, ""));
+                    if let Some(data_helper) = engine.data_helpers.get(helper_name) {
+                        let Some(value) = data_helper(argument.trim()) else {
+                            unresolved.push(UnresolvedName {
+                                name: name.clone(),
+                                location: location.clone(),
+                            });
+                            continue;
+                        };
+                        let items: Vec<&CtxValue> = match &value {
+                            CtxValue::List(items) => items.iter().collect(),
+                            value if is_truthy(Some(value)) => vec![value],
+                            _ => vec![],
+                        };
+                        for item in items {
+                            // the value only lives as long as the section
+                            let mut stack = stack.clone();
+                            stack.push(item);
+                            render_nodes(engine, unresolved, children, &mut stack, blocks, out)?;
+                        }
+                    } else if let Some(helper) = engine.helpers.get(helper_name) {
+                        let content =
+                            render_to_string(engine, unresolved, children, stack, blocks)?;
+                        out.write_all(helper(&content, argument.trim()).as_bytes())?;
+                    } else {
+                        unresolved.push(UnresolvedName {
+                            name: name.clone(),
+                            location: location.clone(),
+                        });
+                    }
+                }
+            },
+            Node::Partial {
+                name,
+                indentation,
+                location,
+            } => {
+                let Some(name) = template_name(stack, unresolved, name, location) else {
+                    continue;
+                };
+                // outside of strict mode partials that can't be found render as an empty string
+                if let Some(partial_nodes) = template_nodes(engine, &name, indentation, location)? {
+                    render_nodes(engine, unresolved, &partial_nodes, stack, blocks, out)?;
+                }
+            }
+            Node::Parent {
+                name,
+                indentation,
+                children,
+                location,
+            } => {
+                let Some(name) = template_name(stack, unresolved, name, location) else {
+                    continue;
+                };
+                if let Some(parent_nodes) = template_nodes(engine, &name, indentation, location)? {
+                    let mut parent_blocks: Blocks = children
+                        .iter()
+                        .filter_map(|child| match child {
+                            Node::Block { name, children, .. } => {
+                                Some((name.clone(), children.clone()))
+                            }
+                            _ => None,
+                        })
+                        .collect();
+                    // overrides from further out win over the ones given here
+                    parent_blocks.extend(blocks.clone());
+                    render_nodes(
+                        engine,
+                        unresolved,
+                        &parent_nodes,
+                        stack,
+                        &parent_blocks,
+                        out,
+                    )?;
+                }
+            }
+            Node::Block {
+                name,
+                indentation,
+                children,
+            } => match blocks.get(name) {
+                Some(overriding_nodes) => {
+                    // a block can't be overridden by itself
+                    let mut inner_blocks = blocks.clone();
+                    inner_blocks.remove(name);
+                    match indentation {
+                        Some(indentation) => {
+                            let block_result = render_to_string(
+                                engine,
+                                unresolved,
+                                overriding_nodes,
+                                stack,
+                                &inner_blocks,
+                            )?;
+                            out.write_all(reindent_lines(&block_result, indentation).as_bytes())?;
+                        }
+                        None => render_nodes(
+                            engine,
+                            unresolved,
+                            overriding_nodes,
+                            stack,
+                            &inner_blocks,
+                            out,
+                        )?,
+                    }
+                }
+                None => render_nodes(engine, unresolved, children, stack, blocks, out)?,
+            },
+        }
+    }
+    Ok(())
+}
+
+/// For output that gets worked on before it's written out (lambdas, helpers and reindented blocks)
+fn render_to_string(
+    engine: &TemplateEngine,
+    unresolved: &mut Vec<UnresolvedName>,
+    nodes: &[Node],
+    stack: &mut Vec<&CtxValue>,
+    blocks: &Blocks,
+) -> Result<String, TemplateError> {
+    let mut buffer = vec![];
+    render_nodes(engine, unresolved, nodes, stack, blocks, &mut buffer)?;
+    Ok(String::from_utf8(buffer).expect("only strs are written"))
+}
+
+/// Partial and parent names starting with a * are dynamic, looked up in the context
+/// IE: {{>*layout}} with a layout of "post" renders the post partial
+fn template_name(
+    stack: &[&CtxValue],
+    unresolved: &mut Vec<UnresolvedName>,
+    name: &str,
+    location: &Location,
+) -> Option<String> {
+    let Some(dynamic_name) = name.strip_prefix('*') else {
+        return Some(name.to_string());
+    };
+    let dynamic_name = dynamic_name.trim();
+    match lookup(stack, dynamic_name) {
+        Some(CtxValue::String(name)) => Some(name.clone()),
+        _ => {
+            unresolved.push(UnresolvedName {
+                name: dynamic_name.to_string(),
+                location: location.clone(),
+            });
+            None
+        }
+    }
+}
+
+/// The nodes of a registered template pulled in as a partial or parent
+/// standalone ones are reparsed so every line gets the tag's indentation
+fn template_nodes<'a>(
+    engine: &'a TemplateEngine,
+    name: &str,
+    indentation: &str,
+    location: &Location,
+) -> Result<Option<Cow<'a, [Node]>>, TemplateError> {
+    let Some(template) = engine.templates.get(name) else {
+        if engine.strict {
+            return Err(TemplateError::new(
+                TemplateErrorKind::UnknownPartial(name.to_string()),
+                location.clone(),
+            ));
+        }
+        return Ok(None);
+    };
+    if indentation.is_empty() {
+        return Ok(Some(Cow::Borrowed(&template.nodes)));
+    }
+    let nodes = parse_template(name, &indent_lines(&template.source, indentation))?;
+    Ok(Some(Cow::Owned(nodes)))
+}
+
+/// Resolves a (possibly dotted) name against the context stack
+/// only the first part of a dotted name looks down the stack, the rest must be found in that value
+fn lookup<'a>(stack: &[&'a CtxValue], name: &str) -> Option<&'a CtxValue> {
+    if name == "." {
+        return stack.last().copied();
+    }
+    let mut keys = name.split('.');
+    let first_key = keys.next()?;
+    let mut value = stack.iter().rev().find_map(|ctx| match ctx {
+        CtxValue::HashMap(map) => map.get(first_key),
+        _ => None,
+    })?;
+    for key in keys {
+        value = match value {
+            CtxValue::HashMap(map) => map.get(key)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+fn is_truthy(value: Option<&CtxValue>) -> bool {
+    match value {
+        None | Some(CtxValue::Null) => false,
+        Some(CtxValue::Boolean(b)) => *b,
+        Some(CtxValue::List(list)) => !list.is_empty(),
+        Some(_) => true,
+    }
+}
+
+pub fn interpolate(value: &CtxValue) -> String {
+    // makes sense for num and string but boolean?? this is smelly
+    match value {
+        CtxValue::String(s) => s.clone(),
+        CtxValue::Number(n) => n.to_string(),
+        CtxValue::Boolean(_)
+        | CtxValue::Null
+        | CtxValue::HashMap(_)
+        | CtxValue::List(_)
+        | CtxValue::Lambda(_) => String::new(),
+    }
+}
+
+/// Prepends indentation to every line of the template (used for standalone partials/parents)
+fn indent_lines(template: &str, indentation: &str) -> String {
+    template
+        .split_inclusive('\n')
+        .map(|line| format!("{indentation}{line}"))
+        .collect()
+}
+
+/// Swaps the indentation an overriding block was written with for the indentation of the block it fills
+fn reindent_lines(block_result: &str, indentation: &str) -> String {
+    let original_indentation: String = block_result
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("")
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect();
+    let mut reindented: String = block_result
+        .split_inclusive('\n')
+        .map(|line| {
+            let line = line.strip_prefix(&original_indentation).unwrap_or(line);
+            if line.trim().is_empty() {
+                line.to_string()
+            } else {
+                format!("{indentation}{line}")
+            }
+        })
+        .collect();
+    // a standalone block takes up whole lines
+    if !reindented.is_empty() && !reindented.ends_with('\n') {
+        reindented.push('\n');
+    }
+    reindented
+}
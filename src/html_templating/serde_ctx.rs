@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use super::{CtxValue, Number};
+
+impl CtxValue {
+    /// Turns any serializable value (like a frontmatter struct) into a context
+    /// goes through serde_yaml's value type since we already depend on it
+    pub fn from_serialize<T: Serialize>(value: &T) -> Result<CtxValue, serde_yaml::Error> {
+        serde_yaml::from_value(serde_yaml::to_value(value)?)
+    }
+}
+
+impl Serialize for CtxValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            CtxValue::String(s) => serializer.serialize_str(s),
+            CtxValue::Boolean(b) => serializer.serialize_bool(*b),
+            CtxValue::Number(Number::Integer(i)) => serializer.serialize_i64(*i),
+            CtxValue::Number(Number::Float(f)) => serializer.serialize_f64(*f),
+            CtxValue::Null => serializer.serialize_unit(),
+            CtxValue::HashMap(map) => {
+                let mut serialize_map = serializer.serialize_map(Some(map.len()))?;
+                for (key, value) in map.iter() {
+                    serialize_map.serialize_entry(key, value)?;
+                }
+                serialize_map.end()
+            }
+            CtxValue::List(list) => {
+                let mut serialize_seq = serializer.serialize_seq(Some(list.len()))?;
+                for value in list.iter() {
+                    serialize_seq.serialize_element(value)?;
+                }
+                serialize_seq.end()
+            }
+            CtxValue::Lambda(_) => Err(ser::Error::custom("lambdas can't be serialized")),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for CtxValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<CtxValue, D::Error> {
+        deserializer.deserialize_any(CtxValueVisitor)
+    }
+}
+
+struct CtxValueVisitor;
+
+impl<'de> Visitor<'de> for CtxValueVisitor {
+    type Value = CtxValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any json/yaml like value")
+    }
+
+    fn visit_bool<E: de::Error>(self, b: bool) -> Result<CtxValue, E> {
+        Ok(CtxValue::Boolean(b))
+    }
+
+    fn visit_i64<E: de::Error>(self, i: i64) -> Result<CtxValue, E> {
+        Ok(CtxValue::Number(Number::Integer(i)))
+    }
+
+    fn visit_u64<E: de::Error>(self, u: u64) -> Result<CtxValue, E> {
+        match i64::try_from(u) {
+            Ok(i) => Ok(CtxValue::Number(Number::Integer(i))),
+            Err(_) => Ok(CtxValue::Number(Number::Float(u as f64))),
+        }
+    }
+
+    fn visit_f64<E: de::Error>(self, f: f64) -> Result<CtxValue, E> {
+        Ok(CtxValue::Number(Number::Float(f)))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<CtxValue, E> {
+        Ok(CtxValue::String(s.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, s: String) -> Result<CtxValue, E> {
+        Ok(CtxValue::String(s))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<CtxValue, E> {
+        Ok(CtxValue::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<CtxValue, E> {
+        Ok(CtxValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<CtxValue, D::Error> {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<CtxValue, A::Error> {
+        let mut list = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element()? {
+            list.push(value);
+        }
+        Ok(CtxValue::List(Box::new(list)))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<CtxValue, A::Error> {
+        let mut map = HashMap::with_capacity(access.size_hint().unwrap_or(0));
+        while let Some((key, value)) = access.next_entry::<CtxValue, CtxValue>()? {
+            map.insert(key_to_string(key)?, value);
+        }
+        Ok(CtxValue::HashMap(Box::new(map)))
+    }
+}
+
+/// Yaml keys can be any scalar (IE: `1: x` or `true: x`), they're looked up as strings
+fn key_to_string<E: de::Error>(key: CtxValue) -> Result<String, E> {
+    match key {
+        CtxValue::String(s) => Ok(s),
+        CtxValue::Number(number) => Ok(number.to_string()),
+        CtxValue::Boolean(b) => Ok(b.to_string()),
+        CtxValue::Null => Ok("null".to_string()),
+        CtxValue::HashMap(_) | CtxValue::List(_) | CtxValue::Lambda(_) => Err(de::Error::custom(
+            "map keys have to be a string, number, bool or null",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Page {
+        title: String,
+        path: std::path::PathBuf,
+        weight: Option<u8>,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    pub fn struct_to_ctx() {
+        let page = Page {
+            title: "Hello".to_string(),
+            path: std::path::PathBuf::from("posts/hello"),
+            weight: None,
+            tags: vec!["rust".to_string()],
+        };
+        let ctx = CtxValue::from_serialize(&page).unwrap();
+        let CtxValue::HashMap(map) = ctx else {
+            panic!("structs should become a map")
+        };
+        assert!(matches!(map.get("title"), Some(CtxValue::String(s)) if s == "Hello"));
+        assert!(matches!(map.get("path"), Some(CtxValue::String(s)) if s == "posts/hello"));
+        assert!(matches!(map.get("weight"), Some(CtxValue::Null)));
+        assert!(matches!(map.get("tags"), Some(CtxValue::List(list)) if list.len() == 1));
+    }
+
+    #[test]
+    pub fn yaml_round_trip() {
+        let ctx: CtxValue = serde_yaml::from_str("a: 1\nb: [1.5, true, ~]\n").unwrap();
+        let yaml = serde_yaml::to_string(&ctx).unwrap();
+        let round_tripped: CtxValue = serde_yaml::from_str(&yaml).unwrap();
+        let CtxValue::HashMap(map) = round_tripped else {
+            panic!("should be a map")
+        };
+        assert!(matches!(
+            map.get("a"),
+            Some(CtxValue::Number(Number::Integer(1)))
+        ));
+        assert!(matches!(map.get("b"), Some(CtxValue::List(list)) if list.len() == 3));
+    }
+
+    #[test]
+    pub fn scalar_keys_become_strings() {
+        let ctx: CtxValue = serde_yaml::from_str(
+            "1: one
+2.5: half
+true: yes
+~: none
+",
+        )
+        .unwrap();
+        let CtxValue::HashMap(map) = ctx else {
+            panic!("should be a map")
+        };
+        let mut keys: Vec<&str> = map.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["1", "2.5", "null", "true"]);
+        assert!(serde_yaml::from_str::<CtxValue>("[a]: x\n").is_err());
+    }
+}
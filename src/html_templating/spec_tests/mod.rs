@@ -1,6 +1,180 @@
-mod comments;
-mod delimiters;
-mod interpolation;
-mod inverted;
-mod partials;
-mod sections;
+//! Runs the mustache spec (https://github.com/mustache/spec) against the engine
+//! The spec's yaml files are read at test time from specs/, each file is one test reporting every failing case
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::Deserialize;
+use serde_yaml::Value;
+
+use super::{CtxValue, TemplateEngine};
+
+#[derive(Deserialize)]
+struct SpecFile {
+    tests: Vec<SpecTest>,
+}
+
+#[derive(Deserialize)]
+struct SpecTest {
+    name: String,
+    desc: String,
+    data: Value,
+    template: String,
+    #[serde(default)]
+    partials: HashMap<String, String>,
+    expected: String,
+}
+
+/// Registered under a name no spec partial uses
+const TEMPLATE_NAME: &str = "spec test";
+
+/// Spec cases the engine is known to fail, as (file, case name, why)
+/// they still run, one that starts passing fails its file's test so it's taken off the list
+/// (empty for now, every case in specs/ passes, see specs/README.md for which files aren't upstream's copies yet)
+const KNOWN_FAILURES: &[(&str, &str, &str)] = &[];
+
+fn run_spec_file(file_name: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/html_templating/spec_tests/specs")
+        .join(file_name);
+    let yaml_str = fs::read_to_string(&path).expect("spec file should be there");
+    let spec_file: SpecFile = serde_yaml::from_str(&yaml_str).expect("spec file should parse");
+
+    let is_known_failure = |test: &SpecTest| {
+        KNOWN_FAILURES
+            .iter()
+            .any(|(known_file, name, _)| *known_file == file_name && *name == test.name)
+    };
+    let failures: Vec<String> = spec_file
+        .tests
+        .iter()
+        .filter_map(|test| match run_spec_test(test) {
+            Ok(result) if result == test.expected && is_known_failure(test) => Some(format!(
+                "{} passes now, take it off KNOWN_FAILURES",
+                test.name
+            )),
+            Ok(_) | Err(_) if is_known_failure(test) => None,
+            Ok(result) if result == test.expected => None,
+            Ok(result) => Some(format!(
+                "{} ({})\ntemplate: {:?}\nexpected: {:?}\n  result: {:?}",
+                test.name, test.desc, test.template, test.expected, result
+            )),
+            Err(e) => Some(format!(
+                "{} ({})\ntemplate: {:?}\n   error: {e}",
+                test.name, test.desc, test.template
+            )),
+        })
+        .collect();
+    assert!(
+        failures.is_empty(),
+        "{} of {} specs in {file_name} failed:\n\n{}",
+        failures.len(),
+        spec_file.tests.len(),
+        failures.join("\n\n")
+    );
+}
+
+fn run_spec_test(test: &SpecTest) -> Result<String, Box<dyn std::error::Error>> {
+    let mut engine = TemplateEngine::new();
+    for (name, partial) in &test.partials {
+        engine.add_template(name, partial.clone())?;
+    }
+    engine.add_template(TEMPLATE_NAME, test.template.clone())?;
+    let context = data_to_ctx(&test.data, &test.name);
+    Ok(engine.render(TEMPLATE_NAME, &context)?)
+}
+
+/// Like deserializing into a CtxValue, except `!code` values (lambdas) are swapped for the test's rust lambda
+fn data_to_ctx(data: &Value, test_name: &str) -> CtxValue {
+    match data {
+        Value::Tagged(_) => spec_lambda(test_name),
+        Value::Mapping(mapping) => CtxValue::HashMap(Box::new(
+            mapping
+                .iter()
+                .map(|(key, value)| {
+                    let key = key.as_str().expect("spec keys are strings").to_string();
+                    (key, data_to_ctx(value, test_name))
+                })
+                .collect(),
+        )),
+        Value::Sequence(list) => CtxValue::List(Box::new(
+            list.iter()
+                .map(|value| data_to_ctx(value, test_name))
+                .collect(),
+        )),
+        _ => serde_yaml::from_value(data.clone()).expect("spec data should be a CtxValue"),
+    }
+}
+
+/// The spec's lambdas are given as code in other languages, these are their rust versions
+fn spec_lambda(test_name: &str) -> CtxValue {
+    match test_name {
+        "Interpolation" => CtxValue::lambda(|_| "world".to_string()),
+        "Interpolation - Expansion" => CtxValue::lambda(|_| "{{planet}}".to_string()),
+        "Interpolation - Alternate Delimiters" => {
+            CtxValue::lambda(|_| "|planet| => {{planet}}".to_string())
+        }
+        "Interpolation - Multiple Calls" => {
+            let calls = AtomicUsize::new(0);
+            CtxValue::lambda(move |_| (calls.fetch_add(1, Ordering::SeqCst) + 1).to_string())
+        }
+        "Escaping" => CtxValue::lambda(|_| ">".to_string()),
+        "Section" => {
+            CtxValue::lambda(|text| if text == "{{x}}" { "yes" } else { "no" }.to_string())
+        }
+        "Section - Expansion" => CtxValue::lambda(|text| format!("{text}{{{{planet}}}}{text}")),
+        "Section - Alternate Delimiters" => {
+            CtxValue::lambda(|text| format!("{text}{{{{planet}}}} => |planet|{text}"))
+        }
+        "Section - Multiple Calls" => CtxValue::lambda(|text| format!("__{text}__")),
+        "Inverted Section" => CtxValue::lambda(|_| String::new()),
+        _ => panic!("No rust lambda for the spec \"{test_name}\""),
+    }
+}
+
+#[test]
+pub fn comments() {
+    run_spec_file("comments.yml");
+}
+
+#[test]
+pub fn delimiters() {
+    run_spec_file("delimiters.yml");
+}
+
+#[test]
+pub fn interpolation() {
+    run_spec_file("interpolation.yml");
+}
+
+#[test]
+pub fn inverted() {
+    run_spec_file("inverted.yml");
+}
+
+#[test]
+pub fn partials() {
+    run_spec_file("partials.yml");
+}
+
+#[test]
+pub fn sections() {
+    run_spec_file("sections.yml");
+}
+
+#[test]
+pub fn lambdas() {
+    run_spec_file("~lambdas.yml");
+}
+
+#[test]
+pub fn inheritance() {
+    run_spec_file("~inheritance.yml");
+}
+
+#[test]
+pub fn dynamic_names() {
+    run_spec_file("~dynamic-names.yml");
+}
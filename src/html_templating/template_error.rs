@@ -0,0 +1,178 @@
+use std::{error::Error, fmt, io};
+
+/// Where a tag sits in it's template
+/// nodes hold on to this so errors found while rendering can point back at the tag
+#[derive(Clone, Debug, PartialEq)]
+pub struct Location {
+    pub template_name: String,
+    /// 1 indexed
+    pub line: usize,
+    /// 1 indexed, in chars
+    pub column: usize,
+    pub source_line: String,
+    /// how many chars of the line to underline
+    pub len: usize,
+}
+
+impl Location {
+    /// start and end are byte idxs into template
+    pub fn new(template_name: &str, template: &str, start: usize, end: usize) -> Location {
+        let line_start = template[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = template[start..]
+            .find('\n')
+            .map_or(template.len(), |i| start + i);
+        Location {
+            template_name: template_name.to_string(),
+            line: template[..start].matches('\n').count() + 1,
+            column: template[line_start..start].chars().count() + 1,
+            source_line: template[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_string(),
+            len: template[start..end.clamp(start, line_end)]
+                .chars()
+                .count()
+                .max(1),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TemplateErrorKind {
+    /// an opening delimiter without it's closing delimiter
+    UnclosedTag,
+    InvalidDelimiters,
+    UnclosedSection(String),
+    MismatchedClosingTag {
+        expected: String,
+        found: String,
+    },
+    UnopenedClosingTag(String),
+    /// only an error in strict mode, otherwise it renders as an empty string
+    UnknownPartial(String),
+    UnknownTemplate(String),
+    /// only an error in strict mode, otherwise these are handed back as warnings
+    UnresolvedNames(Vec<UnresolvedName>),
+    /// writing the output failed (only the message is kept so errors stay comparable)
+    Write(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TemplateError {
+    pub kind: TemplateErrorKind,
+    pub location: Option<Location>,
+}
+
+impl TemplateError {
+    pub fn new(kind: TemplateErrorKind, location: Location) -> TemplateError {
+        TemplateError {
+            kind,
+            location: Some(location),
+        }
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            TemplateErrorKind::UnclosedTag => write!(f, "Tag is missing it's closing delimiter")?,
+            TemplateErrorKind::InvalidDelimiters => write!(
+                f,
+                "Invalid set delimiter tag, expected two delimiters IE: {{{{=<% %>=}}}}"
+            )?,
+            TemplateErrorKind::UnclosedSection(name) => {
+                write!(f, "Section \"{name}\" is never closed")?
+            }
+            TemplateErrorKind::MismatchedClosingTag { expected, found } => write!(
+                f,
+                "Found closing tag \"{found}\" but \"{expected}\" is still open"
+            )?,
+            TemplateErrorKind::UnopenedClosingTag(name) => {
+                write!(f, "Closing tag \"{name}\" doesn't close anything")?
+            }
+            TemplateErrorKind::UnknownPartial(name) => write!(f, "Unknown partial \"{name}\"")?,
+            TemplateErrorKind::UnknownTemplate(name) => write!(f, "Unknown template \"{name}\"")?,
+            TemplateErrorKind::UnresolvedNames(names) => {
+                write!(f, "{} unresolved name(s)", names.len())?;
+                for name in names {
+                    write!(f, "\n\n{name}")?;
+                }
+            }
+            TemplateErrorKind::Write(message) => {
+                write!(f, "Couldn't write the rendered output: {message}")?
+            }
+        };
+        if let Some(location) = &self.location {
+            write_snippet(f, location)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for TemplateError {}
+
+impl From<io::Error> for TemplateError {
+    fn from(e: io::Error) -> TemplateError {
+        TemplateError {
+            kind: TemplateErrorKind::Write(e.to_string()),
+            location: None,
+        }
+    }
+}
+
+/// A variable or section name that isn't in the context (and isn't a helper)
+/// IE: a typo like {{ contnet }}
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnresolvedName {
+    pub name: String,
+    pub location: Location,
+}
+
+impl fmt::Display for UnresolvedName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unresolved name \"{}\"", self.name)?;
+        write_snippet(f, &self.location)
+    }
+}
+
+/// IE:
+/// in template "base" at line 3, column 5
+///   |
+/// 3 | <ul>{{#posts}}
+///   |     ^^^^^^^^^^
+pub fn write_snippet(f: &mut fmt::Formatter<'_>, location: &Location) -> fmt::Result {
+    writeln!(
+        f,
+        " in template \"{}\" at line {}, column {}",
+        location.template_name, location.line, location.column
+    )?;
+    let gutter = " ".repeat(location.line.to_string().len());
+    // keep tabs so the caret lines up with the source line
+    let caret_offset: String = location
+        .source_line
+        .chars()
+        .take(location.column - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    writeln!(f, "{gutter} |")?;
+    writeln!(f, "{} | {}", location.line, location.source_line)?;
+    write!(f, "{gutter} | {caret_offset}{}", "^".repeat(location.len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn snippet_with_caret() {
+        let template = "<html>\n<ul>{{#posts}}\n</ul>";
+        let location = Location::new("base", template, 11, 21);
+        let error = TemplateError::new(
+            TemplateErrorKind::UnclosedSection("posts".to_string()),
+            location,
+        );
+        assert_eq!(
+            error.to_string(),
+            "Section \"posts\" is never closed in template \"base\" at line 2, column 5\n  |\n2 | <ul>{{#posts}}\n  |     ^^^^^^^^^^"
+        )
+    }
+}
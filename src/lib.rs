@@ -3,4 +3,5 @@ pub mod cli;
 pub mod html_templating;
 pub mod markdown_parsing;
 pub mod proj_config_utils;
+pub mod threads;
 pub mod web_server;
@@ -1,7 +1,10 @@
 use clap::Parser;
-use megalopa::build::build;
+use megalopa::build::{build, BuildOptions};
 use megalopa::cli::{self, Cli, Command};
+use megalopa::proj_config_utils::find_project_dir;
 use megalopa::web_server;
+use std::path::PathBuf;
+use std::{env, process};
 
 fn main() {
     let cli = Cli::parse();
@@ -10,9 +13,70 @@ fn main() {
         Command::New {
             title,
             content_name,
-        } => cli::write_file::write_file(title, content_name).unwrap(),
-        Command::Dev { port } => web_server::start_dev_server(port),
-        Command::Build { empty_out_dir } => build(empty_out_dir),
-        Command::Init { project_name } => cli::init::init_project(project_name),
+        } => cli::write_file::write_file(&project_dir(cli.project), title, content_name).unwrap(),
+        Command::Dev { port } => web_server::start_dev_server(project_dir(cli.project), port),
+        Command::Build {
+            output,
+            clean,
+            drafts,
+            future,
+            force,
+        } => {
+            let options = BuildOptions {
+                // relative to where it's run, like any other path on the command line
+                output: output
+                    .map(|output| env::current_dir().expect("You have no cwd?").join(output)),
+                clean,
+                drafts,
+                future,
+                force,
+            };
+            match build(&project_dir(cli.project), options) {
+                Ok(report) if report.is_success() => println!("{report}"),
+                // the pages that could be built were, but the build still failed
+                Ok(report) => {
+                    eprintln!("{report}");
+                    process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("Build failed: {e}");
+                    process::exit(1);
+                }
+            }
+        }
+        Command::Init {
+            project_name,
+            eject_templates,
+        } => cli::init::init_project(project_name, eject_templates),
+        Command::Render {
+            template,
+            data,
+            partials,
+            strict,
+            no_escape,
+        } => {
+            if let Err(e) = cli::render::render(
+                &template,
+                data.as_deref(),
+                partials.as_deref(),
+                strict,
+                !no_escape,
+            ) {
+                eprintln!("Render failed: {e}");
+                process::exit(1);
+            }
+        }
     }
 }
+
+/// The --project dir or the cwd, walked up to the project's root
+fn project_dir(project: Option<PathBuf>) -> PathBuf {
+    let start = match project {
+        Some(project) => project,
+        None => env::current_dir().expect("You have no cwd?"),
+    };
+    find_project_dir(&start).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        process::exit(1);
+    })
+}
@@ -34,11 +34,11 @@ impl ToHtml for SyntaxNode {
                 .expect("Text node should have content")
                 .to_string(),
             NodeType::Code => {
-                // wrap with <code> block
+                // wrap with <code> block, what's in it is shown as is (IE: `<tag>`) so it's escaped
                 let mut wrapped_contents = String::from("<code>");
                 self.children
                     .iter()
-                    .for_each(|child| wrapped_contents.push_str(child.to_html().as_str()));
+                    .for_each(|child| wrapped_contents.push_str(&escape_code(&child.to_html())));
                 wrapped_contents.push_str("</code>");
                 wrapped_contents
             }
@@ -182,6 +182,12 @@ impl ToHtml for SyntaxNode {
     }
 }
 
+fn escape_code(code: &str) -> String {
+    code.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
 #[cfg(test)]
 mod tests {
     use std::vec;
@@ -215,6 +221,17 @@ mod tests {
         };
 
         assert_eq!(node.to_html(), "<code>code</code>");
+
+        let node: SyntaxNode = SyntaxNode {
+            content: None,
+            children: Box::new(vec![SyntaxNode {
+                content: Some(String::from("a && <b>")),
+                children: Box::default(),
+                node_type: NodeType::Text,
+            }]),
+            node_type: NodeType::Code,
+        };
+        assert_eq!(node.to_html(), "<code>a &amp;&amp; &lt;b&gt;</code>");
     }
     #[test]
     pub fn bold_to_html() {
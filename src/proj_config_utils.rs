@@ -1,37 +1,429 @@
+use serde::{Deserialize, Serialize};
 use serde_yaml;
-use serde::Deserialize;
-use std::{env, fs, path};
-
-/// Checks for a config file to verify we are in a project dir
-/// # Panics
-/// Panics if a project config file is not found in the cwd (we are not in a project dir)
-pub fn get_project_dir() -> path::PathBuf {
-    let cwd = env::current_dir().expect("You have no cwd?");
-    let config_file_path = cwd.join("larvae.yaml");
-
-    // likely should do an assert here
-    match fs::read_to_string(config_file_path) {
-        Ok(_) => {}
-        Err(_) => {
-            panic!("Couldn't find your config file, are you in the project's root dir?")
-        }
-    };
+use std::{collections::BTreeMap, error::Error, fmt, fs, io, path};
 
-    cwd
+/// Finds the project's root dir by walking up from start until a dir has a config file (like git)
+pub fn find_project_dir(start: &path::Path) -> io::Result<path::PathBuf> {
+    let start = start.canonicalize()?;
+    start
+        .ancestors()
+        .find(|dir| dir.join("larvae.yaml").is_file())
+        .map(path::Path::to_path_buf)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "Couldn't find a larvae.yaml in {} or any of its parent dirs, are you in a project?",
+                    start.display()
+                ),
+            )
+        })
 }
-#[derive(Debug, Deserialize)]
+
+/// larvae.yaml, everything but title is optional
+/// (serialized for templates as the site object IE: {{ site.base_url }})
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
     pub title: String,
+    /// IE: https://example.com, for links that have to be absolute (no trailing slash)
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    /// The html lang attribute
+    #[serde(default = "default_language")]
+    pub language: String,
+    #[serde(default)]
+    pub template_engine: TemplateEngineKind,
+    /// Mustache templates error on names missing from the context instead of warning about them
+    /// (tera is always strict)
+    #[serde(default)]
+    pub strict_templates: bool,
+    #[serde(default)]
+    pub dirs: DirsConfig,
+    /// The template content pages of a section use instead of "content", by the section's path in content
+    /// IE: `posts: post` renders content/posts/*.md with the post template
+    #[serde(default)]
+    pub section_templates: BTreeMap<String, String>,
+    #[serde(default)]
+    pub taxonomies: Vec<TaxonomyConfig>,
+    #[serde(default)]
+    pub build: BuildConfig,
+    #[serde(default)]
+    pub feeds: FeedsConfig,
+    #[serde(default)]
+    pub sitemap: SitemapConfig,
+}
+
+fn default_language() -> String {
+    "en".to_string()
+}
+
+/// Names of the project's dirs (relative to the project dir)
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DirsConfig {
+    pub content: String,
+    pub output: String,
+    #[serde(rename = "static")]
+    pub static_files: String,
+    pub templates: String,
+}
+
+impl Default for DirsConfig {
+    fn default() -> Self {
+        DirsConfig {
+            content: "content".to_string(),
+            output: "public".to_string(),
+            static_files: "static".to_string(),
+            templates: "templates".to_string(),
+        }
+    }
+}
+
+/// A way of grouping pages (IE: tags), listed in a page's frontmatter under it's name
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TaxonomyConfig {
+    pub name: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BuildConfig {
+    /// Remove everything the last build wrote before every build, not just on `megalopa build --clean`
+    #[serde(alias = "empty_out_dir")]
+    pub clean: bool,
+}
+
+/// rss.xml and atom.xml at the site's root and in each section
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FeedsConfig {
+    /// Needs base_url, feeds only have absolute links
+    pub enabled: bool,
+    /// How many of the newest pages a feed lists
+    pub limit: usize,
+    /// Whole pages instead of their description (or the start of the page)
+    pub full_content: bool,
+}
+
+impl Default for FeedsConfig {
+    fn default() -> Self {
+        FeedsConfig {
+            enabled: false,
+            limit: 20,
+            full_content: false,
+        }
+    }
+}
+
+/// sitemap.xml (and robots.txt) at the site's root, only built when there's a base_url
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SitemapConfig {
+    pub enabled: bool,
+    /// A robots.txt allowing everything and pointing at the sitemap (a robots.txt in static replaces it)
+    pub robots_txt: bool,
+}
+
+impl Default for SitemapConfig {
+    fn default() -> Self {
+        SitemapConfig {
+            enabled: true,
+            robots_txt: false,
+        }
+    }
+}
+
+/// Which engine renders the templates dir, tera (*.html) or our own mustache engine (*.mustache)
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateEngineKind {
+    #[default]
+    Tera,
+    Mustache,
+}
+
+/// A larvae.yaml that couldn't be read, parsed or didn't pass validation
+#[derive(Debug)]
+pub struct ConfigError {
+    pub message: String,
+    /// The offending line, if we can point at one
+    pub line: Option<ConfigLine>,
+}
+
+/// A key in larvae.yaml we didn't expect (likely a typo)
+#[derive(Debug)]
+pub struct ConfigWarning {
+    pub message: String,
+    pub line: Option<ConfigLine>,
 }
-/// Reads larvae.yaml
-/// # Panics
-/// Panics if a project config file is not found in the cwd (we are not in a project dir)
-pub fn read_config(project_dir: &path::PathBuf) -> Config {
+
+#[derive(Debug, PartialEq)]
+pub struct ConfigLine {
+    /// 1 based
+    pub number: usize,
+    pub source: String,
+}
+
+impl ConfigLine {
+    fn new(yaml_str: &str, number: usize) -> Option<ConfigLine> {
+        let source = yaml_str.lines().nth(number.checked_sub(1)?)?;
+        Some(ConfigLine {
+            number,
+            source: source.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        write_config_line(f, self.line.as_ref())
+    }
+}
+
+impl Error for ConfigError {}
+
+impl From<ConfigError> for io::Error {
+    fn from(e: ConfigError) -> Self {
+        io::Error::other(e.to_string())
+    }
+}
+
+impl fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        write_config_line(f, self.line.as_ref())
+    }
+}
+
+fn write_config_line(f: &mut fmt::Formatter<'_>, line: Option<&ConfigLine>) -> fmt::Result {
+    let Some(line) = line else {
+        return write!(f, " in larvae.yaml");
+    };
+    writeln!(f, " in larvae.yaml at line {}", line.number)?;
+    let gutter = " ".repeat(line.number.to_string().len());
+    writeln!(f, "{gutter} |")?;
+    write!(f, "{} | {}", line.number, line.source)
+}
+
+/// Reads larvae.yaml, handing back warnings for any keys we don't know about
+pub fn read_config(project_dir: &path::Path) -> Result<(Config, Vec<ConfigWarning>), ConfigError> {
     let config_file_path = project_dir.join("larvae.yaml");
-    if let Ok(yaml_str) = fs::read_to_string(config_file_path) {
-        let config: Config = serde_yaml::from_str(&yaml_str).unwrap();
-        config
-    } else {
-        panic!("Couldn't find your config file, are you in the project's root dir?")
+    let yaml_str = fs::read_to_string(&config_file_path).map_err(|e| ConfigError {
+        message: format!("Couldn't read {}: {e}", config_file_path.display()),
+        line: None,
+    })?;
+    parse_config(&yaml_str)
+}
+
+/// Parses and validates the contents of larvae.yaml
+pub fn parse_config(yaml_str: &str) -> Result<(Config, Vec<ConfigWarning>), ConfigError> {
+    let mut unknown_keys = vec![];
+    let deserializer = serde_yaml::Deserializer::from_str(yaml_str);
+    let mut config: Config = serde_ignored::deserialize(deserializer, |path| {
+        unknown_keys.push(path.to_string());
+    })
+    .map_err(|e| ConfigError {
+        message: format!("Invalid config: {e}"),
+        line: e
+            .location()
+            .and_then(|location| ConfigLine::new(yaml_str, location.line())),
+    })?;
+
+    let warnings = unknown_keys
+        .into_iter()
+        .map(|key| ConfigWarning {
+            line: key_line(yaml_str, &key),
+            message: format!("Unknown key \"{key}\""),
+        })
+        .collect();
+
+    validate_config(&mut config).map_err(|(key, message)| ConfigError {
+        message: format!("Invalid config: {message}"),
+        line: key_line(yaml_str, key),
+    })?;
+    Ok((config, warnings))
+}
+
+/// Checks what serde can't, normalizing along the way
+/// errors are the (dotted) key at fault and what's wrong with it
+fn validate_config(config: &mut Config) -> Result<(), (&'static str, String)> {
+    if config.title.trim().is_empty() {
+        return Err(("title", "title can't be empty".to_string()));
+    }
+    if let Some(base_url) = &mut config.base_url {
+        if !(base_url.starts_with("http://") || base_url.starts_with("https://")) {
+            return Err((
+                "base_url",
+                format!("base_url has to start with http:// or https://, found \"{base_url}\""),
+            ));
+        }
+        *base_url = base_url.trim_end_matches('/').to_string();
+    }
+    if config.language.is_empty() || config.language.contains(char::is_whitespace) {
+        return Err((
+            "language",
+            format!(
+                "language has to be a language code IE: en, found \"{}\"",
+                config.language
+            ),
+        ));
+    }
+
+    let dirs = [
+        ("dirs.content", &config.dirs.content),
+        ("dirs.output", &config.dirs.output),
+        ("dirs.static", &config.dirs.static_files),
+        ("dirs.templates", &config.dirs.templates),
+    ];
+    for (i, (key, dir)) in dirs.iter().enumerate() {
+        let mut components = path::Path::new(dir).components();
+        let is_dir_name = matches!(
+            (components.next(), components.next()),
+            (Some(path::Component::Normal(_)), None)
+        );
+        if !is_dir_name {
+            return Err((key, format!("{key} has to be a dir name, found \"{dir}\"")));
+        }
+        if let Some((other_key, _)) = dirs[..i].iter().find(|(_, other)| other == dir) {
+            return Err((key, format!("{key} and {other_key} can't be the same dir")));
+        }
+    }
+
+    if config.feeds.enabled && config.base_url.is_none() {
+        return Err((
+            "feeds.enabled",
+            "feeds need a base_url for their links".to_string(),
+        ));
+    }
+    if config.feeds.limit == 0 {
+        return Err(("feeds.limit", "feeds.limit has to be at least 1".to_string()));
+    }
+    if config.sitemap.robots_txt && !(config.sitemap.enabled && config.base_url.is_some()) {
+        return Err((
+            "sitemap.robots_txt",
+            "robots.txt points at the sitemap, it needs a base_url and sitemap.enabled".to_string(),
+        ));
+    }
+
+    for (i, taxonomy) in config.taxonomies.iter().enumerate() {
+        if taxonomy.name.is_empty() || slug::slugify(&taxonomy.name) != taxonomy.name {
+            return Err((
+                "taxonomies",
+                format!(
+                    "taxonomy names have to be lowercase words joined by dashes IE: blog-tags, found \"{}\"",
+                    taxonomy.name
+                ),
+            ));
+        }
+        if config.taxonomies[..i]
+            .iter()
+            .any(|other| other.name == taxonomy.name)
+        {
+            return Err((
+                "taxonomies",
+                format!("the taxonomy \"{}\" is listed twice", taxonomy.name),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Finds the line of a dotted key (IE: dirs.content) by following the yaml's indentation
+/// list indices in the key are skipped, the line of the list is close enough
+fn key_line(yaml_str: &str, dotted_key: &str) -> Option<ConfigLine> {
+    let mut lines = yaml_str.lines().enumerate();
+    let mut parent_indent = None;
+    let mut found = None;
+    for key in dotted_key
+        .split('.')
+        .filter(|key| key.parse::<usize>().is_err())
+    {
+        let (number, indent) = lines.find_map(|(i, line)| {
+            let trimmed = line.trim_start();
+            let indent = line.len() - trimmed.len();
+            let is_key = trimmed
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.trim_start().starts_with(':'));
+            let is_nested = parent_indent.map_or(indent == 0, |parent| indent > parent);
+            (is_key && is_nested).then_some((i + 1, indent))
+        })?;
+        parent_indent = Some(indent);
+        found = Some(number);
+    }
+    ConfigLine::new(yaml_str, found?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn finds_the_project_dir_from_a_sub_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let proj_dir = dir.path().join("site");
+        let sub_dir = proj_dir.join("content/posts");
+        fs::create_dir_all(&sub_dir).unwrap();
+        fs::write(proj_dir.join("larvae.yaml"), "title: site\n").unwrap();
+
+        let found = find_project_dir(&sub_dir).unwrap();
+        assert_eq!(found, proj_dir.canonicalize().unwrap());
+        assert_eq!(find_project_dir(&proj_dir).unwrap(), found);
+        let err = find_project_dir(dir.path()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    pub fn defaults_and_unknown_keys() {
+        let yaml_str = "title: site\nbase_url: https://example.com/\ndirs:\n  output: dist\n  contnet: posts\nthemes: dark\n";
+        let (config, warnings) = parse_config(yaml_str).unwrap();
+        assert_eq!(config.base_url.as_deref(), Some("https://example.com"));
+        assert_eq!(config.language, "en");
+        assert_eq!(config.dirs.content, "content");
+        assert_eq!(config.dirs.output, "dist");
+        assert_eq!(config.template_engine, TemplateEngineKind::Tera);
+
+        let warnings: Vec<(String, usize)> = warnings
+            .into_iter()
+            .map(|warning| (warning.message, warning.line.unwrap().number))
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                ("Unknown key \"dirs.contnet\"".to_string(), 5),
+                ("Unknown key \"themes\"".to_string(), 6)
+            ]
+        );
+    }
+
+    #[test]
+    pub fn errors_point_at_the_line() {
+        let err = parse_config("title: site\nbase_url: example.com\n").unwrap_err();
+        assert_eq!(
+            err.line,
+            Some(ConfigLine {
+                number: 2,
+                source: "base_url: example.com".to_string()
+            })
+        );
+        assert_eq!(
+            err.to_string(),
+            "Invalid config: base_url has to start with http:// or https://, found \"example.com\" in larvae.yaml at line 2\n  |\n2 | base_url: example.com"
+        );
+
+        let err = parse_config("title: site\ndirs:\n  static: public\n").unwrap_err();
+        assert_eq!(err.line.unwrap().number, 3);
+        let err = parse_config("title: site\ntaxonomies:\n  - name: Tags\n").unwrap_err();
+        assert_eq!(err.line.unwrap().number, 2);
+        let err = parse_config("title: site\nfeeds:\n  enabled: true\n").unwrap_err();
+        assert_eq!(err.line.unwrap().number, 3);
+        // serde's own errors keep their line
+        let err = parse_config("title: site\nstrict_templates: sometimes\n").unwrap_err();
+        assert_eq!(err.line.unwrap().number, 2);
+        assert!(parse_config("base_url: https://example.com\n").is_err());
     }
 }
@@ -1,5 +1,8 @@
 use std::{
-    sync::{mpsc, Arc, Mutex},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
     thread,
 };
 
@@ -85,3 +88,58 @@ impl Worker {
 }
 
 type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Runs f on every job across size threads, unlike ThreadPool's jobs f can borrow from the caller
+/// the results come back in the same order as the jobs (whichever thread finishes first)
+///
+/// # Panics
+///
+/// Panics if the size is zero, or if f panics
+pub fn map_in_parallel<J, R, F>(size: usize, jobs: &[J], f: F) -> Vec<R>
+where
+    J: Sync,
+    R: Send,
+    F: Fn(&J) -> R + Sync,
+{
+    assert!(size > 0);
+    let next_job = AtomicUsize::new(0);
+    let mut results: Vec<Option<R>> = jobs.iter().map(|_| None).collect();
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..size.min(jobs.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut worker_results = vec![];
+                    // each thread takes the next job as soon as it's free
+                    loop {
+                        let i = next_job.fetch_add(1, Ordering::Relaxed);
+                        let Some(job) = jobs.get(i) else {
+                            break;
+                        };
+                        worker_results.push((i, f(job)));
+                    }
+                    worker_results
+                })
+            })
+            .collect();
+        for worker in workers {
+            for (i, result) in worker.join().unwrap() {
+                results[i] = Some(result);
+            }
+        }
+    });
+    results.into_iter().map(Option::unwrap).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn results_keep_the_jobs_order() {
+        let jobs: Vec<usize> = (0..100).collect();
+        let offset = 1;
+        let results = map_in_parallel(4, &jobs, |job| job + offset);
+        assert_eq!(results, (1..101).collect::<Vec<usize>>());
+        assert!(map_in_parallel(4, &[] as &[usize], |job| *job).is_empty());
+    }
+}
@@ -1,22 +1,31 @@
 mod get_content_type_from_file_extension;
 mod open_websocket;
 mod parse_http_headers;
-mod threads;
 
-use crate::build::build;
-use crate::proj_config_utils::get_project_dir;
+use crate::build::{build, output_dir, render_page, BuildOptions};
+use crate::proj_config_utils::{read_config, DirsConfig};
+use crate::threads;
 use get_content_type_from_file_extension::get_content_type_from_file_extension;
 
 use http_bytes::http::{Response, StatusCode};
 use http_bytes::response_header_to_vec;
 use httparse;
-use std::io::Write;
+use std::io::{BufWriter, Write};
 use std::net::{TcpListener, TcpStream};
-use std::path::PathBuf;
-use std::{self, fs};
+use std::path::{Path, PathBuf};
+use std::{self, fs, io};
 
-pub fn start_dev_server(port: u16) {
-    build(false);
+/// The dev server shows drafts (with a banner) so they can be previewed
+const DEV_BUILD_OPTIONS: BuildOptions = BuildOptions {
+    output: None,
+    clean: false,
+    drafts: true,
+    future: false,
+    force: false,
+};
+
+pub fn start_dev_server(proj_dir: PathBuf, port: u16) {
+    dev_build(&proj_dir);
     let mut addr = "127.0.0.1:".to_owned();
     addr.push_str(&port.to_string());
 
@@ -29,15 +38,33 @@ pub fn start_dev_server(port: u16) {
 
     for stream in listener.incoming() {
         let stream = stream.unwrap();
-        pool.execute(|| {
-            handle_connection(stream);
+        let proj_dir = proj_dir.clone();
+        pool.execute(move || {
+            handle_connection(stream, &proj_dir);
         })
     }
     println!("Shutting down")
 }
-fn handle_connection(mut stream: TcpStream) {
-    let cwd = get_project_dir();
 
+/// Builds with DEV_BUILD_OPTIONS, printing what failed
+/// keep serving on a broken build, the next save can fix it
+fn dev_build(proj_dir: &Path) {
+    match build(proj_dir, DEV_BUILD_OPTIONS) {
+        Ok(report) if report.is_success() => println!("{report}"),
+        Ok(report) => eprintln!("{report}"),
+        Err(e) => eprintln!("Build failed: {e}"),
+    }
+}
+
+/// larvae.yaml's dirs, read each time they're needed so changes to it are picked up
+/// (the defaults when it can't be read, the build says why)
+fn dirs(proj_dir: &Path) -> DirsConfig {
+    read_config(proj_dir)
+        .map(|(config, _)| config.dirs)
+        .unwrap_or_default()
+}
+
+fn handle_connection(mut stream: TcpStream, proj_dir: &Path) {
     let mut recieved = [0u8; 1000];
     let bytes_peeked = stream.peek(&mut recieved).unwrap();
     assert_ne!(bytes_peeked, 0);
@@ -54,7 +81,7 @@ fn handle_connection(mut stream: TcpStream) {
         if let (Some(_), Some(upgrade_header)) = (headers.get("Connection"), headers.get("Upgrade"))
         {
             if upgrade_header.eq(&"websocket") {
-                open_websocket::open_websocket(&stream);
+                open_websocket::open_websocket(&stream, proj_dir);
                 return;
             }
         }
@@ -66,7 +93,20 @@ fn handle_connection(mut stream: TcpStream) {
             req_path = req_path
                 .strip_prefix("/")
                 .expect("req_path isn't relative...");
-            let path = cwd.join("public").join(req_path);
+            let mut path = output_dir(proj_dir, &dirs(proj_dir), &DEV_BUILD_OPTIONS).join(req_path);
+            if path.is_dir() {
+                path = path.join("index.html");
+            } else if path.extension().is_none() && !path.exists() {
+                // pages are linked to without their .html
+                path.set_extension("html");
+            }
+            // big pages are written to the connection as they're written out, not read back from disk after
+            stream.set_nonblocking(false).unwrap();
+            if path.extension().is_some_and(|ext| ext == "html")
+                && handle_page_req(&stream, proj_dir, &path)
+            {
+                return;
+            }
             handle_get_req(stream, path);
             return;
         }
@@ -80,24 +120,94 @@ fn handle_connection(mut stream: TcpStream) {
     stream.write_all(&res_vec).unwrap();
 }
 
-fn handle_get_req(mut stream: TcpStream, mut path: PathBuf) {
-    if path.is_dir() {
-        path = path.join("index.html");
+/// Renders the page built to path straight into the connection, false if there isn't one (it's served from the output dir)
+fn handle_page_req(stream: &TcpStream, proj_dir: &Path, path: &Path) -> bool {
+    let mut response = BufWriter::new(PageResponse {
+        stream,
+        has_started: false,
+    });
+    let rendered = render_page(proj_dir, DEV_BUILD_OPTIONS, path, &mut response);
+    // what's still buffered is only sent if the page rendered
+    let (mut page_response, buffered) = response.into_parts();
+    let sent = match rendered {
+        Ok(false) => return false,
+        Ok(true) => page_response
+            .start()
+            .and_then(|_| page_response.write_all(&buffered.unwrap_or_default())),
+        Err(e) if !page_response.has_started => {
+            let message = format!("Build failed: {e}");
+            eprintln!("{message}");
+            let response_500: Response<()> = Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .header("Content-Type", "text/plain")
+                .header("content-length", message.len())
+                .body(())
+                .unwrap();
+            let mut res_vec = response_header_to_vec(&response_500);
+            res_vec.extend(message.as_bytes());
+            page_response.stream.write_all(&res_vec)
+        }
+        // the page is cut short, it's too late for an error response
+        Err(e) => {
+            eprintln!("Build failed: {e}");
+            Ok(())
+        }
+    };
+    if let Err(e) = sent {
+        eprintln!("Couldn't send {}: {e}", path.display());
     }
+    true
+}
+
+/// Sends a 200's headers just before the first of the page, so a page that fails before any of it's
+/// rendered can still get an error response (the connection's closed to end it, it's length isn't known up front)
+struct PageResponse<'a> {
+    stream: &'a TcpStream,
+    has_started: bool,
+}
+
+impl PageResponse<'_> {
+    fn start(&mut self) -> io::Result<()> {
+        if !self.has_started {
+            let res: Response<()> = Response::builder()
+                .status(StatusCode::OK)
+                .header("Content-Type", "text/html")
+                .header("Connection", "close")
+                .body(())
+                .unwrap();
+            self.stream.write_all(&response_header_to_vec(&res))?;
+            self.has_started = true;
+        }
+        Ok(())
+    }
+}
+
+impl Write for PageResponse<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.start()?;
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
 
+fn handle_get_req(mut stream: TcpStream, path: PathBuf) {
     if path.exists() {
         let content_type = get_content_type_from_file_extension(path.extension().unwrap());
-        let contents = fs::read(&path).unwrap();
-        let length = contents.len();
-        let res = Response::builder()
+        let mut file = fs::File::open(&path).unwrap();
+        let length = file.metadata().unwrap().len();
+        let res: Response<()> = Response::builder()
             .status(StatusCode::OK)
             .header("Content-Type", content_type)
             .header("content-length", length)
-            .body(contents)
+            .body(())
             .unwrap();
-        let mut res_vec = response_header_to_vec(&res);
-        res_vec.write(res.body()).unwrap();
-        stream.write_all(&res_vec).unwrap();
+        stream.write_all(&response_header_to_vec(&res)).unwrap();
+        // stream the body instead of reading big pages into memory first
+        io::copy(&mut file, &mut stream).unwrap();
+        return;
     }
 
     let response_404: Response<()> = Response::builder()
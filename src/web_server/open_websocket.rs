@@ -1,10 +1,10 @@
-use crate::{build, proj_config_utils::get_project_dir};
 use notify::{RecursiveMode, Watcher};
-use std::{net::TcpStream, time::Duration};
+use std::{net::TcpStream, path::Path, time::Duration};
 
-pub fn open_websocket(stream: &TcpStream) -> () {
-    let cwd = get_project_dir();
-    let content_dir = cwd.join("content");
+pub fn open_websocket(stream: &TcpStream, proj_dir: &Path) {
+    let dirs = super::dirs(proj_dir);
+    let content_dir = proj_dir.join(&dirs.content);
+    let build_dir = proj_dir.to_path_buf();
 
     let mut socket = tungstenite::accept(stream).unwrap();
     enum Message {
@@ -15,20 +15,23 @@ pub fn open_websocket(stream: &TcpStream) -> () {
         notify::recommended_watcher(move |res: Result<notify::Event, notify::Error>| match res {
             Ok(event) => {
                 if event.kind.is_modify() {
-                    build::build(false);
+                    super::dev_build(&build_dir);
                     tx.send(Message::FileChanged).unwrap();
                 }
             }
             Err(e) => println!("watch error: {:?}", e),
         })
         .unwrap();
-    let temp_root_dir = content_dir.parent().unwrap().parent().unwrap();
-    let templates_dir = temp_root_dir.join("templates");
-    let static_dir = temp_root_dir.join("static");
+    let templates_dir = proj_dir.join(&dirs.templates);
+    let static_dir = proj_dir.join(&dirs.static_files);
 
     watcher.watch(&content_dir, RecursiveMode::Recursive).unwrap();
-    watcher.watch(&templates_dir, RecursiveMode::Recursive).unwrap();
-    watcher.watch(&static_dir, RecursiveMode::Recursive).unwrap();
+    // templates and static are optional (projects without templates use the built in ones)
+    for optional_dir in [&templates_dir, &static_dir] {
+        if optional_dir.is_dir() {
+            watcher.watch(optional_dir, RecursiveMode::Recursive).unwrap();
+        }
+    }
 
     println!("Websocket opening...");
     loop {
@@ -59,5 +62,4 @@ pub fn open_websocket(stream: &TcpStream) -> () {
     }
 
     println!("Websocket closing...");
-    return;
 }